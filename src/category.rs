@@ -1,5 +1,5 @@
 use std::{
-    collections::{hash_map::Entry, hash_set, HashMap, HashSet},
+    collections::{hash_map, hash_map::Entry, HashMap},
     fmt::Debug,
     hash::Hash,
 };
@@ -7,7 +7,7 @@ use thiserror::Error;
 
 use crate::{
     impls::SimpleMorphism,
-    morphism::{Morphism, MorphismMeta},
+    morphism::{HasLogic, KeyOf, Morphism, MorphismMeta},
 };
 
 pub trait Object<Id: Key>: HasId<Id> + Clone {}
@@ -28,10 +28,14 @@ pub trait Key: Eq + Hash + Debug + Clone {}
 impl<K: Eq + Hash + Debug + Clone> Key for K {}
 
 #[derive(Clone, Debug)]
-pub struct Category<Id = String, M = SimpleMorphism, Obj = Id> {
+pub struct Category<Id = String, M = SimpleMorphism, Obj = Id>
+where
+    M: MorphismMeta,
+{
     objects: HashMap<Id, Obj>,
-    morphisms: HashSet<Morphism<Id, M>>,
-    outbound: HashMap<Id, Vec<Morphism<Id, M>>>,
+    morphisms: HashMap<KeyOf<Id, M>, Morphism<Id, M>>,
+    outbound: HashMap<Id, Vec<KeyOf<Id, M>>>,
+    inbound: HashMap<Id, Vec<KeyOf<Id, M>>>,
 }
 
 impl<Id, M> From<Vec<Morphism<Id, M>>> for Category<Id, M, Id>
@@ -53,10 +57,11 @@ where
     pub fn from_morphisms(morphisms: impl IntoIterator<Item = Morphism<Id, M>>) -> Self {
         let mut new = Self::new();
         for morphism in morphisms {
-            new.objects
-                .insert(morphism.source.clone(), morphism.source.clone());
-            new.objects
-                .insert(morphism.target.clone(), morphism.target.clone());
+            for id in [&morphism.source, &morphism.target] {
+                new.objects.insert(id.clone(), id.clone());
+                new.outbound.entry(id.clone()).or_default();
+                new.inbound.entry(id.clone()).or_default();
+            }
             new.add_morphism_unchecked(morphism);
         }
         new
@@ -74,17 +79,25 @@ where
     }
 }
 
-impl<Id, M, Obj> Category<Id, M, Obj> {
+impl<Id, M, Obj> Category<Id, M, Obj>
+where
+    M: MorphismMeta,
+{
     pub fn new() -> Self {
         Self {
             objects: HashMap::new(),
-            morphisms: HashSet::new(),
+            morphisms: HashMap::new(),
             outbound: HashMap::new(),
+            inbound: HashMap::new(),
         }
     }
 
-    pub fn morphisms(&self) -> hash_set::Iter<Morphism<Id, M>> {
-        self.morphisms.iter()
+    pub fn objects(&self) -> hash_map::Values<'_, Id, Obj> {
+        self.objects.values()
+    }
+
+    pub fn morphisms(&self) -> hash_map::Values<'_, KeyOf<Id, M>, Morphism<Id, M>> {
+        self.morphisms.values()
     }
 
     #[allow(clippy::type_complexity)]
@@ -92,16 +105,18 @@ impl<Id, M, Obj> Category<Id, M, Obj> {
         self,
     ) -> (
         HashMap<Id, Obj>,
-        HashSet<Morphism<Id, M>>,
-        HashMap<Id, Vec<Morphism<Id, M>>>,
+        HashMap<KeyOf<Id, M>, Morphism<Id, M>>,
+        HashMap<Id, Vec<KeyOf<Id, M>>>,
+        HashMap<Id, Vec<KeyOf<Id, M>>>,
     ) {
-        (self.objects, self.morphisms, self.outbound)
+        (self.objects, self.morphisms, self.outbound, self.inbound)
     }
 }
 
 impl<Id, M, Obj> Category<Id, M, Obj>
 where
     Id: Key,
+    M: MorphismMeta,
 {
     pub fn of(
         objects: impl IntoIterator<Item = Obj>,
@@ -109,7 +124,6 @@ where
     ) -> Result<Self, CategoryError>
     where
         Obj: HasId<Id>,
-        M: MorphismMeta,
     {
         let mut new = Self::new();
         new.add_objects(objects)?;
@@ -134,10 +148,7 @@ where
     pub fn add_morphisms(
         &mut self,
         morphisms: impl IntoIterator<Item = Morphism<Id, M>>,
-    ) -> Result<(), CategoryError>
-    where
-        M: MorphismMeta,
-    {
+    ) -> Result<(), CategoryError> {
         for morphism in morphisms {
             self.add_morphism(morphism)?;
         }
@@ -155,28 +166,27 @@ where
                 x.insert(object);
             }
         };
-        if self.outbound.insert(id, Vec::new()).is_some() {
+        if self.outbound.insert(id.clone(), Vec::new()).is_some()
+            || self.inbound.insert(id, Vec::new()).is_some()
+        {
             unreachable!("Category has a bug. This entry should have been empty.")
         }
 
         Ok(())
     }
 
-    pub fn verify_morphism(&self, morphism: &Morphism<Id, M>) -> Result<(), CategoryError>
-    where
-        M: MorphismMeta,
-    {
-        if self.morphisms.contains(morphism) {
+    pub fn verify_morphism(&self, morphism: &Morphism<Id, M>) -> Result<(), CategoryError> {
+        if self.morphisms.contains_key(&morphism.key()) {
             return Err(MorphismAlreadyInserted(
                 format!("{:?}", morphism.source),
-                format!("{:?}", morphism.source),
+                format!("{:?}", morphism.target),
             ));
         }
         let mut missing = vec![];
-        if self.objects.get(&morphism.source).is_none() {
+        if !self.objects.contains_key(&morphism.source) {
             missing.push(format!("source: {:?}", morphism.source));
         }
-        if self.objects.get(&morphism.target).is_none() {
+        if !self.objects.contains_key(&morphism.target) {
             missing.push(format!("target: {:?}", morphism.target));
         }
         if !missing.is_empty() {
@@ -186,37 +196,199 @@ where
         Ok(())
     }
 
-    pub fn add_morphism(&mut self, morphism: Morphism<Id, M>) -> Result<(), CategoryError>
-    where
-        M: MorphismMeta,
-    {
+    pub fn add_morphism(&mut self, morphism: Morphism<Id, M>) -> Result<(), CategoryError> {
         self.verify_morphism(&morphism)?;
         self.add_morphism_unchecked(morphism);
         Ok(())
     }
 
-    fn add_morphism_unchecked(&mut self, morphism: Morphism<Id, M>)
+    fn add_morphism_unchecked(&mut self, morphism: Morphism<Id, M>) {
+        let key = morphism.key();
+        match self.outbound.entry(morphism.source.clone()) {
+            Entry::Occupied(mut x) => x.get_mut().push(key.clone()),
+            Entry::Vacant(x) => x.insert(vec![]).push(key.clone()),
+        }
+        match self.inbound.entry(morphism.target.clone()) {
+            Entry::Occupied(mut x) => x.get_mut().push(key.clone()),
+            Entry::Vacant(x) => x.insert(vec![]).push(key.clone()),
+        }
+        self.morphisms.insert(key, morphism);
+    }
+
+    pub fn get_morphism(&self, key: &KeyOf<Id, M>) -> Option<&Morphism<Id, M>> {
+        self.morphisms.get(key)
+    }
+
+    /// Changes the cost logic of a morphism in place. The morphism keeps its
+    /// identity, so it does not need to be removed and re-added.
+    ///
+    /// Returns an error and leaves the morphism unchanged if the update
+    /// changes the Key of its metadata.
+    pub fn update_morphism(
+        &mut self,
+        key: &KeyOf<Id, M>,
+        f: impl FnOnce(&mut M::Logic),
+    ) -> Result<(), CategoryError>
     where
-        M: MorphismMeta,
+        M: HasLogic,
     {
-        self.morphisms.insert(morphism.clone());
-        match self.outbound.entry(morphism.source.clone()) {
-            Entry::Occupied(mut x) => x.get_mut().push(morphism),
-            Entry::Vacant(x) => x.insert(vec![]).push(morphism),
+        let Some(morphism) = self.morphisms.get_mut(key) else {
+            return Err(MissingMorphism(format!("{:?}", key)));
+        };
+        let mut metadata = morphism.metadata.clone();
+        f(metadata.logic_mut());
+        if metadata.key() != &key.key {
+            return Err(KeyChanged(
+                format!("{:?}", key),
+                format!("{:?}", metadata.key()),
+            ));
+        }
+        morphism.metadata = metadata;
+        Ok(())
+    }
+
+    /// Removes the morphism from the category and returns it.
+    pub fn remove_morphism(
+        &mut self,
+        key: &KeyOf<Id, M>,
+    ) -> Result<Morphism<Id, M>, CategoryError> {
+        let Some(removed) = self.morphisms.remove(key) else {
+            return Err(MissingMorphism(format!("{:?}", key)));
+        };
+        if let Some(outbound) = self.outbound.get_mut(&key.source) {
+            outbound.retain(|k| k != key);
+        }
+        if let Some(inbound) = self.inbound.get_mut(&key.target) {
+            inbound.retain(|k| k != key);
+        }
+        Ok(removed)
+    }
+
+    /// Removes the object from the category and returns it, along with any
+    /// morphisms that were removed because they used the object as their
+    /// source or target.
+    ///
+    /// The policy determines what happens when there are morphisms that touch
+    /// the object. They are either removed alongside the object, or the removal
+    /// is refused with an error.
+    #[allow(clippy::type_complexity)]
+    pub fn remove_object(
+        &mut self,
+        id: &Id,
+        policy: RemovalPolicy,
+    ) -> Result<(Obj, Vec<Morphism<Id, M>>), CategoryError> {
+        if !self.objects.contains_key(id) {
+            return Err(MissingObjects(vec![format!("{:?}", id)]));
+        }
+        let mut touching = self.outbound[id].clone();
+        touching.extend(self.inbound[id].iter().filter(|k| &k.source != id).cloned());
+        if !touching.is_empty() && policy == RemovalPolicy::Refuse {
+            return Err(ObjectHasMorphisms(format!("{:?}", id), touching.len()));
+        }
+        let removed = touching
+            .iter()
+            .map(|key| self.remove_morphism(key))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.outbound.remove(id);
+        self.inbound.remove(id);
+        let object = self
+            .objects
+            .remove(id)
+            .expect("Category has a bug. The object was checked above.");
+
+        Ok((object, removed))
+    }
+
+    /// Keeps only the morphisms for which the predicate returns true. All
+    /// other morphisms are removed from the category. Objects are not removed,
+    /// even if they no longer have any morphisms.
+    pub fn retain_morphisms(&mut self, mut predicate: impl FnMut(&Morphism<Id, M>) -> bool) {
+        self.morphisms.retain(|_, m| predicate(m));
+        for adjacent in self.outbound.values_mut().chain(self.inbound.values_mut()) {
+            adjacent.retain(|k| self.morphisms.contains_key(k));
         }
     }
 
-    pub fn get_outbound(&self, id: &Id) -> Option<&Vec<Morphism<Id, M>>> {
-        self.outbound.get(id)
+    pub fn get_outbound(&self, id: &Id) -> Option<impl Iterator<Item = &Morphism<Id, M>>> {
+        self.outbound
+            .get(id)
+            .map(|keys| keys.iter().map(|k| &self.morphisms[k]))
+    }
+
+    /// Returns every morphism that has this object as its target.
+    pub fn get_inbound(&self, id: &Id) -> Option<impl Iterator<Item = &Morphism<Id, M>>> {
+        self.inbound
+            .get(id)
+            .map(|keys| keys.iter().map(|k| &self.morphisms[k]))
+    }
+
+    /// Returns every morphism from source to target, also known as the
+    /// hom-set of the two objects.
+    pub fn hom<'a>(
+        &'a self,
+        source: &'a Id,
+        target: &'a Id,
+    ) -> impl Iterator<Item = &'a Morphism<Id, M>> {
+        self.get_outbound(source)
+            .into_iter()
+            .flatten()
+            .filter(move |m| &m.target == target)
     }
 
     pub fn get_object(&self, id: &Id) -> Option<&Obj> {
         self.objects.get(id)
     }
+}
+
+/// Serialized as a list of objects and a list of morphisms. The adjacency
+/// indexes are not serialized, since they are derived from the morphisms.
+#[cfg(feature = "serde")]
+impl<Id, M, Obj> serde::Serialize for Category<Id, M, Obj>
+where
+    Id: serde::Serialize,
+    M: MorphismMeta + serde::Serialize,
+    Obj: serde::Serialize,
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("Category", 2)?;
+        state.serialize_field("objects", &self.objects.values().collect::<Vec<_>>())?;
+        state.serialize_field("morphisms", &self.morphisms.values().collect::<Vec<_>>())?;
+        state.end()
+    }
+}
+
+/// Rebuilds the category with `Category::of`, so every object and morphism is
+/// verified the same way as if it were added directly.
+#[cfg(feature = "serde")]
+impl<'de, Id, M, Obj> serde::Deserialize<'de> for Category<Id, M, Obj>
+where
+    Id: Key + serde::Deserialize<'de>,
+    M: MorphismMeta + serde::Deserialize<'de>,
+    Obj: HasId<Id> + serde::Deserialize<'de>,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(bound(deserialize = "Id: serde::Deserialize<'de>, \
+            M: serde::Deserialize<'de>, Obj: serde::Deserialize<'de>"))]
+        struct Raw<Id, M, Obj> {
+            objects: Vec<Obj>,
+            morphisms: Vec<Morphism<Id, M>>,
+        }
+        let raw = Raw::<Id, M, Obj>::deserialize(deserializer)?;
+        Self::of(raw.objects, raw.morphisms).map_err(serde::de::Error::custom)
+    }
+}
 
-    // pub fn objects(&self) -> hash_map::Iter<Id, Rc<Object>> {
-    //     self.objects.iter().map(Clone::clone)
-    // }
+/// Determines what `Category::remove_object` does with the morphisms that use
+/// the object as their source or target.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RemovalPolicy {
+    /// Remove every morphism that touches the object, along with the object.
+    Cascade,
+    /// Return an error and leave the category untouched if any morphism
+    /// touches the object.
+    Refuse,
 }
 
 /// todo smarter about debug and string and types etc
@@ -228,5 +400,11 @@ pub enum CategoryError {
     MorphismAlreadyInserted(String, String),
     #[error("The objects were expected but not found: {0:?}")]
     MissingObjects(Vec<String>),
+    #[error("This morphism was expected but not found: {0:?}")]
+    MissingMorphism(String),
+    #[error("The object {0:?} cannot be removed because it is used by {1} morphisms")]
+    ObjectHasMorphisms(String, usize),
+    #[error("Updating the morphism {0:?} would change its key to {1:?}")]
+    KeyChanged(String, String),
 }
 use CategoryError::*;
@@ -66,11 +66,11 @@ impl<T> SomeVec<T> {
         }
     }
 
-    pub fn iter(&self) -> Chain<Iter<T>, Iter<T>> {
+    pub fn iter(&self) -> Chain<Iter<'_, T>, Iter<'_, T>> {
         self.start.iter().chain(self.rest.iter())
     }
 
-    pub fn iter_rest(&self) -> Iter<T> {
+    pub fn iter_rest(&self) -> Iter<'_, T> {
         self.rest.iter()
     }
 }
@@ -81,7 +81,7 @@ impl<T> IntoIterator for SomeVec<T> {
     type IntoIter = Chain<array::IntoIter<T, 1>, vec::IntoIter<T>>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.start.into_iter().chain(self.rest.into_iter())
+        self.start.into_iter().chain(self.rest)
     }
 }
 
@@ -100,6 +100,24 @@ impl<T> TryFrom<Vec<T>> for SomeVec<T> {
     }
 }
 
+/// Serialized as an ordinary sequence
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize> serde::Serialize for SomeVec<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+/// Fails if the sequence is empty
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for SomeVec<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<T>::deserialize(deserializer)?
+            .try_into()
+            .map_err(|_| serde::de::Error::invalid_length(0, &"at least one element"))
+    }
+}
+
 impl<T: Debug> Debug for SomeVec<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_list()
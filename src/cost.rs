@@ -0,0 +1,122 @@
+//! The requirements for a Cost to be used by every optimizer, and for the
+//! vector-valued costs used by `ParetoOptimizer`.
+
+use std::{
+    fmt::Debug,
+    ops::{Add, Sub},
+};
+
+/// A cost that can be summed and totally ordered. Each optimization backend
+/// has its own traits for costs, and they are adapted from this trait
+/// internally, so any CostMeasure works with every optimizer.
+pub trait CostMeasure: Copy + Ord + Debug + Add<Output = Self> {
+    fn zero() -> Self;
+
+    /// Represents an unreachable cost. It must be greater than any cost that
+    /// could be found by summing the costs of morphisms.
+    fn infinite() -> Self;
+
+    /// Adds two costs. The optimizers add costs with this rather than `+`, so
+    /// that integer costs saturate at their minimum and maximum instead of
+    /// overflowing.
+    fn plus(self, other: Self) -> Self {
+        self + other
+    }
+
+    /// Subtracts a cost, for the searches that need subtraction. Like `plus`,
+    /// integer costs saturate instead of overflowing.
+    fn minus(self, other: Self) -> Self
+    where
+        Self: Sub<Output = Self>,
+    {
+        self - other
+    }
+
+    fn sum(costs: impl IntoIterator<Item = Self>) -> Self {
+        costs.into_iter().fold(Self::zero(), Self::plus)
+    }
+}
+
+macro_rules! impl_integer {
+    ($($T:ty),*$(,)*) => {
+        $(
+            impl CostMeasure for $T {
+                fn zero() -> Self {
+                    0
+                }
+
+                fn infinite() -> Self {
+                    <$T>::MAX
+                }
+
+                fn plus(self, other: Self) -> Self {
+                    self.saturating_add(other)
+                }
+
+                fn minus(self, other: Self) -> Self {
+                    self.saturating_sub(other)
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+/// A cost with several components that are minimized together, such as a fee
+/// and a number of hops. Vector costs are only partially ordered: one cost
+/// dominates another if it is no worse in every component and better in at
+/// least one.
+///
+/// It is implemented for arrays and tuples of CostMeasures.
+pub trait VectorCost: Clone + Debug {
+    fn zero() -> Self;
+
+    /// Adds each component of `other` to the same component of `self`.
+    fn combine(&self, other: &Self) -> Self;
+
+    /// True if every component of `self` is no greater than the same component
+    /// of `other`.
+    fn weakly_dominates(&self, other: &Self) -> bool;
+
+    /// True if `self` weakly dominates `other` and they are not equal.
+    fn dominates(&self, other: &Self) -> bool {
+        self.weakly_dominates(other) && !other.weakly_dominates(self)
+    }
+}
+
+impl<C: CostMeasure, const N: usize> VectorCost for [C; N] {
+    fn zero() -> Self {
+        [C::zero(); N]
+    }
+
+    fn combine(&self, other: &Self) -> Self {
+        std::array::from_fn(|i| self[i].plus(other[i]))
+    }
+
+    fn weakly_dominates(&self, other: &Self) -> bool {
+        self.iter().zip(other).all(|(a, b)| a <= b)
+    }
+}
+
+macro_rules! impl_tuple {
+    ($(($($T:ident $i:tt),*)),*$(,)*) => {
+        $(
+            impl<$($T: CostMeasure),*> VectorCost for ($($T,)*) {
+                fn zero() -> Self {
+                    ($($T::zero(),)*)
+                }
+
+                fn combine(&self, other: &Self) -> Self {
+                    ($(self.$i.plus(other.$i),)*)
+                }
+
+                fn weakly_dominates(&self, other: &Self) -> bool {
+                    $(self.$i <= other.$i)&&*
+                }
+            }
+        )*
+    };
+}
+
+impl_tuple!((A 0, B 1), (A 0, B 1, C 2), (A 0, B 1, C 2, D 3));
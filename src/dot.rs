@@ -0,0 +1,127 @@
+//! Renders categories in the Graphviz DOT language, for debugging and
+//! visualizing the paths selected by the optimizers.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{Display, Write},
+};
+
+use crate::{
+    category::{Category, HasId, Key},
+    morphism::{KeyOf, MorphismMeta},
+    shortest_path::path::AppliedCompositeMorphism,
+};
+
+impl<Id, M, Obj> Category<Id, M, Obj>
+where
+    Id: Key + Display,
+    M: MorphismMeta + Display,
+    Obj: HasId<Id>,
+{
+    /// Renders every object as a node and every morphism as an edge labeled
+    /// with the morphism's metadata.
+    pub fn to_dot(&self) -> String {
+        self.render_dot(&HashSet::new(), &[])
+    }
+
+    /// Renders the category like `to_dot`, and highlights the morphisms and
+    /// objects from the path. Each highlighted morphism is labeled with its
+    /// position in the path, its input and output sizes, and its cost.
+    /// A morphism that the path uses more than once is drawn as a single edge,
+    /// with one label line for each use.
+    pub fn to_dot_with_path<Size, Cost>(
+        &self,
+        path: impl Into<AppliedCompositeMorphism<Id, M, Obj, Size, Cost>>,
+    ) -> String
+    where
+        Size: Display,
+        Cost: Display,
+    {
+        let path = path.into();
+        let mut objects = HashSet::new();
+        let mut edges: Vec<(KeyOf<Id, M>, String)> = vec![];
+        let mut positions: HashMap<KeyOf<Id, M>, usize> = HashMap::new();
+        for (step, applied) in path.morphisms.iter().enumerate() {
+            objects.insert(applied.source.0.id());
+            objects.insert(applied.target.0.id());
+            let key = applied.morphism.key();
+            let label = format!(
+                "{}: {}\n{} ─▶ {}\ncost: {}",
+                step + 1,
+                applied.morphism.metadata,
+                applied.source.1,
+                applied.target.1,
+                applied.cost
+            );
+            match positions.get(&key) {
+                Some(&position) => {
+                    let labels = &mut edges[position].1;
+                    labels.push('\n');
+                    labels.push_str(&label);
+                }
+                None => {
+                    positions.insert(key.clone(), edges.len());
+                    edges.push((key, label));
+                }
+            }
+        }
+        self.render_dot(&objects, &edges)
+    }
+
+    fn render_dot(
+        &self,
+        highlighted_objects: &HashSet<Id>,
+        highlighted_edges: &[(KeyOf<Id, M>, String)],
+    ) -> String {
+        let highlighted_keys = highlighted_edges
+            .iter()
+            .map(|(key, _)| key)
+            .collect::<HashSet<_>>();
+        let mut dot = String::from("digraph {\n");
+        for object in self.objects() {
+            let id = object.id();
+            let style = if highlighted_objects.contains(&id) {
+                ", color=red, penwidth=2"
+            } else {
+                ""
+            };
+            writeln!(dot, "    {} [label={}{}];", quote(&id), quote(&id), style).unwrap();
+        }
+        for morphism in self.morphisms() {
+            let key = morphism.key();
+            if highlighted_keys.contains(&key) {
+                continue;
+            }
+            writeln!(
+                dot,
+                "    {} -> {} [label={}];",
+                quote(&morphism.source),
+                quote(&morphism.target),
+                quote(&morphism.metadata)
+            )
+            .unwrap();
+        }
+        for (key, label) in highlighted_edges {
+            writeln!(
+                dot,
+                "    {} -> {} [label={}, color=red, fontcolor=red, penwidth=2];",
+                quote(&key.source),
+                quote(&key.target),
+                quote(label)
+            )
+            .unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+fn quote(item: &impl Display) -> String {
+    format!(
+        "\"{}\"",
+        item.to_string()
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    )
+}
@@ -7,6 +7,8 @@ use ordered_float::OrderedFloat;
 use pathfinding::num_traits::Zero;
 use petgraph::algo::FloatMeasure;
 
+use crate::cost::CostMeasure;
+
 pub const ZERO: Float = float(0.0);
 pub const ONE: Float = float(1.0);
 pub const INFINITY: Float = float(f64::INFINITY);
@@ -40,6 +42,21 @@ pub const fn float(x: f64) -> Float {
     Float(OrderedFloat(x))
 }
 
+/// Serialized as a plain f64
+#[cfg(feature = "serde")]
+impl serde::Serialize for Float {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.to_f64())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Float {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        f64::deserialize(deserializer).map(float)
+    }
+}
+
 impl Debug for Float {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "Float({:?})", self.0)
@@ -77,6 +94,15 @@ impl Zero for Float {
     }
 }
 
+impl CostMeasure for Float {
+    fn zero() -> Self {
+        ZERO
+    }
+    fn infinite() -> Self {
+        INFINITY
+    }
+}
+
 impl FloatMeasure for Float {
     fn zero() -> Self {
         ZERO
@@ -1,15 +1,17 @@
-use std::fmt::Debug;
+use std::fmt::{Debug, Display};
 use std::hash::Hash;
 
-use crate::morphism::{ApplyMorphism, MorphismMeta, MorphismOutput};
+use crate::category::Key;
+use crate::morphism::{ApplyMorphism, HasLogic, InputIndependent, MorphismMeta, MorphismOutput};
 
 use super::Float;
 
 /// This can be used as the metadata field in Morphism.
 #[derive(Clone, Debug, Hash, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SimpleMorphism<Meta = String, Logic = ConstantCost>
 where
-    Meta: MorphismMeta,
+    Meta: Key,
 {
     /// This should be sufficiently unique to distinguish the morphism from
     /// other morphisms that have the same source and target.
@@ -17,7 +19,7 @@ where
     pub logic: Logic,
 }
 
-impl<Meta: MorphismMeta> SimpleMorphism<Meta> {
+impl<Meta: Key> SimpleMorphism<Meta> {
     pub fn new(meta: Meta) -> Self {
         Self {
             meta,
@@ -26,10 +28,43 @@ impl<Meta: MorphismMeta> SimpleMorphism<Meta> {
     }
 }
 
+impl<Meta, Logic> Display for SimpleMorphism<Meta, Logic>
+where
+    Meta: Key + Display,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.meta, f)
+    }
+}
+
+impl<Meta, Logic> MorphismMeta for SimpleMorphism<Meta, Logic>
+where
+    Meta: Key,
+    Logic: Clone,
+{
+    type Key = Meta;
+
+    fn key(&self) -> &Meta {
+        &self.meta
+    }
+}
+
+impl<Meta, Logic> HasLogic for SimpleMorphism<Meta, Logic>
+where
+    Meta: Key,
+    Logic: Clone,
+{
+    type Logic = Logic;
+
+    fn logic_mut(&mut self) -> &mut Logic {
+        &mut self.logic
+    }
+}
+
 impl<Size, Cost, Meta, Logic, const NON_NEGATIVE: bool> ApplyMorphism<Size, Cost, NON_NEGATIVE>
     for SimpleMorphism<Meta, Logic>
 where
-    Meta: MorphismMeta,
+    Meta: Key,
     Logic: ApplyMorphism<Size, Cost, NON_NEGATIVE>,
 {
     fn apply(&self, input: Size) -> MorphismOutput<Size, Cost> {
@@ -37,7 +72,15 @@ where
     }
 }
 
+impl<Meta, Logic> InputIndependent for SimpleMorphism<Meta, Logic>
+where
+    Meta: Key,
+    Logic: InputIndependent,
+{
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DeductiveLinearCost {
     pub rate: Float,
     pub constant: Float,
@@ -56,6 +99,7 @@ impl ApplyMorphism<Float, Float, true> for DeductiveLinearCost {
 
 /// Every morphism is always a cost of 1, for a basic unweighted graph.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ConstantCost;
 
 impl ApplyMorphism<(), Float, true> for ConstantCost {
@@ -66,3 +110,5 @@ impl ApplyMorphism<(), Float, true> for ConstantCost {
         }
     }
 }
+
+impl InputIndependent for ConstantCost {}
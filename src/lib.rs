@@ -1,7 +1,10 @@
 pub mod category;
 pub mod collections;
+pub mod cost;
+mod dot;
 pub mod impls;
 pub mod morphism;
+pub mod propagation;
 pub mod shortest_path;
 pub mod vertex;
 
@@ -1,29 +1,108 @@
-use std::{fmt::Display, hash::Hash};
+use std::{convert::Infallible, fmt::Display, hash::Hash};
 
 use pathfinding::num_traits::Zero;
 
 use crate::{
     category::{Category, HasId, Key},
     collections::SomeVec,
+    cost::CostMeasure,
     impls::Float,
+    propagation::SizePropagation,
 };
 
-pub trait MorphismMeta: Hash + Eq + Clone {}
-impl<M> MorphismMeta for M where M: Hash + Eq + Clone {}
+/// The metadata of a morphism. A morphism's identity within a category is
+/// determined by its source, its target, and the Key of its metadata. Anything
+/// else contained in the metadata may be changed without changing the identity
+/// of the morphism.
+///
+/// If the entire metadata is needed to identify the morphism, use `Self` as
+/// the Key, which `self_keyed!` implements for you.
+pub trait MorphismMeta: Clone {
+    type Key: Key;
+    fn key(&self) -> &Self::Key;
+}
 
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+/// Implements MorphismMeta for metadata types that are their own Key, so the
+/// entire metadata identifies the morphism. The types must implement Hash, Eq,
+/// Clone, and Debug.
+///
+/// ```
+/// #[derive(Clone, Debug, Hash, PartialEq, Eq)]
+/// struct Pool {
+///     name: &'static str,
+///     fee: u64,
+/// }
+///
+/// optimorph::self_keyed!(Pool);
+/// ```
+#[macro_export]
+macro_rules! self_keyed {
+    ($($t:ty),+ $(,)?) => {
+        $(
+            impl $crate::morphism::MorphismMeta for $t {
+                type Key = Self;
+
+                fn key(&self) -> &Self {
+                    self
+                }
+            }
+        )+
+    };
+}
+
+/// Metadata with cost logic that can be mutated in place, without changing
+/// the Key of the metadata.
+pub trait HasLogic: MorphismMeta {
+    type Logic;
+    fn logic_mut(&mut self) -> &mut Self::Logic;
+}
+
+/// Two morphisms are equal if they have the same source, target, and Key, even
+/// if the rest of their metadata is different.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Morphism<Id, M> {
     pub source: Id,
     pub target: Id,
     /// This should contain:
     /// - Uniquely identifying information that distinguishes this morphism from
-    ///   other morphisms with the same source and target. Eq and Hash values
-    ///   must be unique.
+    ///   other morphisms with the same source and target. This is exposed as
+    ///   the Key of MorphismMeta, whose Eq and Hash values must be unique.
     /// - Logic to determine cost and output size from applying the morphism. It
     ///   should implement some variant of ApplyMorphism in order to be useful.
     pub metadata: M,
 }
 
+impl<Id: PartialEq, M: MorphismMeta> PartialEq for Morphism<Id, M> {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+            && self.target == other.target
+            && self.metadata.key() == other.metadata.key()
+    }
+}
+
+impl<Id: Eq, M: MorphismMeta> Eq for Morphism<Id, M> {}
+
+impl<Id: Hash, M: MorphismMeta> Hash for Morphism<Id, M> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.source.hash(state);
+        self.target.hash(state);
+        self.metadata.key().hash(state);
+    }
+}
+
+/// Uniquely identifies a morphism within a category.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MorphismKey<Id, K> {
+    pub source: Id,
+    pub target: Id,
+    pub key: K,
+}
+
+/// The MorphismKey of a Morphism<Id, M>
+pub type KeyOf<Id, M> = MorphismKey<Id, <M as MorphismMeta>::Key>;
+
 impl<Id: Display, M: Display> Display for Morphism<Id, M> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let alt = f.alternate();
@@ -69,25 +148,46 @@ impl<Id, M> Morphism<Id, M> {
         }
     }
 
+    pub fn key(&self) -> KeyOf<Id, M>
+    where
+        Id: Clone,
+        M: MorphismMeta,
+    {
+        MorphismKey {
+            source: self.source.clone(),
+            target: self.target.clone(),
+            key: self.metadata.key().clone(),
+        }
+    }
+
     /// Needed for `pathfinding`
-    pub(crate) fn successor<const NON_NEGATIVE: bool, Obj, Size: Clone, Cost>(
+    ///
+    /// Returns the target, the size of the target according to the propagation
+    /// policy, and the cost of this morphism. `original` is the input size of
+    /// the entire path. Returns None if the morphism cannot be applied to the
+    /// input.
+    pub(crate) fn successor<const NON_NEGATIVE: bool, Obj, Size: Clone, Cost, P>(
         &self,
         category: &Category<Id, M, Obj>,
         input_size: Size,
-    ) -> (Id, Size, Cost)
+        original: &Size,
+        propagation: &P,
+    ) -> Option<(Id, Size, Cost)>
     where
-        M: ApplyMorphism<Size, Cost, NON_NEGATIVE>,
+        M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
         Id: Key,
         Obj: HasId<Id>,
+        P: SizePropagation<Obj, Size>,
     {
         // todo find a way to get a compile-time guarantee that unwrap cannot fail
-        // todo should apply have access to these states?
-        let _input_object = category.get_object(&self.source).unwrap();
-        let _output_object = category.get_object(&self.target).unwrap();
-        let output = self.metadata.apply(input_size);
-        //todo configurable: replace by output, do not touch, set to constant
-        // next_object.size = output.size;
-        (self.target.clone(), output.size, output.cost)
+        let input_object = category.get_object(&self.source).unwrap();
+        let output_object = category.get_object(&self.target).unwrap();
+        let output = self
+            .metadata
+            .try_apply(input_object, output_object, input_size.clone())
+            .ok()?;
+        let size = propagation.propagate(output_object, original, &input_size, output.size);
+        Some((self.target.clone(), size, output.cost))
     }
 }
 
@@ -107,10 +207,119 @@ impl<Id, M> Morphism<Id, M> {
 ///
 /// This guarantee cannot be provided by the compiler. Implement this trait at
 /// your own risk.
+///
+/// # Monotonicity
+/// The MaxOutput optimizer additionally requires that a larger input never
+/// results in a smaller output. For any two Sizes s1 and s2 where s1 <= s2:
+/// * apply(s1).size <= apply(s2).size
+///
+/// Most exchange rates and fee schedules satisfy this. Like NON_NEGATIVE, it
+/// is not checked by the compiler, and it is not required by any other
+/// optimizer. The same contract applies to ApplyMorphismWithContext and to
+/// every input where TryApplyMorphism does not fail. If a morphism fails for a
+/// larger input but not for a smaller one, MaxOutput may not find paths that
+/// depend on the smaller input.
 pub trait ApplyMorphism<Size = Float, Cost = Float, const NON_NEGATIVE: bool = false> {
     fn apply(&self, input: Size) -> MorphismOutput<Size, Cost>;
 }
 
+/// Like ApplyMorphism, but the source and target objects of the morphism are
+/// also provided. Use this when the cost or output size depends on properties
+/// of the objects, rather than storing copies of those properties in every
+/// morphism.
+///
+/// It is implemented automatically for every ApplyMorphism by ignoring the
+/// objects, so you should implement only one of the two traits. The
+/// NON_NEGATIVE promise is the same as for ApplyMorphism.
+pub trait ApplyMorphismWithContext<
+    Obj,
+    Size = Float,
+    Cost = Float,
+    const NON_NEGATIVE: bool = false,
+>
+{
+    fn apply_with_context(
+        &self,
+        source: &Obj,
+        target: &Obj,
+        input: Size,
+    ) -> MorphismOutput<Size, Cost>;
+}
+
+impl<T, Obj, Size, Cost, const NON_NEGATIVE: bool>
+    ApplyMorphismWithContext<Obj, Size, Cost, NON_NEGATIVE> for T
+where
+    T: ApplyMorphism<Size, Cost, NON_NEGATIVE>,
+{
+    fn apply_with_context(
+        &self,
+        _source: &Obj,
+        _target: &Obj,
+        input: Size,
+    ) -> MorphismOutput<Size, Cost> {
+        self.apply(input)
+    }
+}
+
+/// Like ApplyMorphismWithContext, but the morphism may fail to apply to some
+/// inputs. For example, a pool cannot exchange more than its liquidity. Use
+/// this instead of returning an infinite or otherwise fake cost.
+///
+/// When a morphism fails, the optimizers treat it as absent for that input, so
+/// no path is found through it. The optimizers and `reapply` always apply
+/// morphisms through this trait.
+///
+/// It is implemented automatically for every ApplyMorphismWithContext, which
+/// never fails, so you should implement only one of these traits. The
+/// NON_NEGATIVE promise is the same as for ApplyMorphism, for every input that
+/// does not fail.
+pub trait TryApplyMorphism<Obj, Size = Float, Cost = Float, const NON_NEGATIVE: bool = false> {
+    type Error;
+
+    fn try_apply(
+        &self,
+        source: &Obj,
+        target: &Obj,
+        input: Size,
+    ) -> Result<MorphismOutput<Size, Cost>, Self::Error>;
+}
+
+impl<T, Obj, Size, Cost, const NON_NEGATIVE: bool> TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>
+    for T
+where
+    T: ApplyMorphismWithContext<Obj, Size, Cost, NON_NEGATIVE>,
+{
+    type Error = Infallible;
+
+    fn try_apply(
+        &self,
+        source: &Obj,
+        target: &Obj,
+        input: Size,
+    ) -> Result<MorphismOutput<Size, Cost>, Infallible> {
+        Ok(self.apply_with_context(source, target, input))
+    }
+}
+
+/// Reports the resources that a morphism consumes when it is applied, such as
+/// compute units, in addition to its cost. This is used by the
+/// ResourceConstrained optimizer, which keeps the total resources of a path
+/// within limits. Resources are typically a VectorCost such as an array, so
+/// that each kind of resource has its own limit.
+pub trait ConsumeResources<Obj, Size, Resources> {
+    fn resources(&self, source: &Obj, target: &Obj, input: &Size) -> Resources;
+}
+
+/// A promise that the cost of a morphism, and whether it can be applied, do
+/// not depend on the size of its input. The output size may still change, but
+/// it never affects the cost of the path. This allows the Bidirectional
+/// optimizer to search backwards from the target, where the input of each
+/// morphism is not known yet.
+///
+/// Like NON_NEGATIVE, this cannot be checked by the compiler. It is true of
+/// every morphism whose Size is `()`, such as ConstantCost.
+pub trait InputIndependent {}
+
 pub struct MorphismOutput<Size = Float, Cost = Float> {
     pub size: Size,
     pub cost: Cost,
@@ -127,15 +336,22 @@ impl<Size, Cost: Zero> MorphismOutput<Size, Cost> {
 
 pub struct CompositeMorphism<Id, M>(pub SomeVec<Morphism<Id, M>>);
 
+/// Applies each morphism to the output size of the previous one. The cost is
+/// the sum of the costs of every morphism.
 impl<Id, M, Size, Cost, const NON_NEGATIVE: bool> ApplyMorphism<Size, Cost, NON_NEGATIVE>
     for CompositeMorphism<Id, M>
 where
     M: ApplyMorphism<Size, Cost, NON_NEGATIVE>,
+    Cost: CostMeasure,
 {
     fn apply(&self, input: Size) -> MorphismOutput<Size, Cost> {
         let mut output = self.0.first().metadata.apply(input);
         for item in self.0.iter_rest() {
-            output = item.metadata.apply(output.size);
+            let next = item.metadata.apply(output.size);
+            output = MorphismOutput {
+                size: next.size,
+                cost: output.cost.plus(next.cost),
+            };
         }
         output
     }
@@ -0,0 +1,66 @@
+//! Policies that decide the size of each object in a path from the output of
+//! the morphism that leads to it. The size of an object is the input for the
+//! next morphism in the path.
+//!
+//! The first object in a path always has the input size that was provided to
+//! the optimizer. The policy determines the size of every object after it.
+
+/// Determines the size of a morphism's target object.
+pub trait SizePropagation<Obj, Size> {
+    /// - `target`: the object that will be given the returned size.
+    /// - `original`: the input size that was provided for the entire path.
+    /// - `input`: the input size of the morphism that leads to `target`.
+    /// - `output`: the output size of that morphism.
+    fn propagate(&self, target: &Obj, original: &Size, input: &Size, output: Size) -> Size;
+}
+
+/// Each morphism's output is used as the input of the next morphism. This is
+/// the default policy.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Accumulate;
+
+impl<Obj, Size> SizePropagation<Obj, Size> for Accumulate {
+    fn propagate(&self, _target: &Obj, _original: &Size, _input: &Size, output: Size) -> Size {
+        output
+    }
+}
+
+/// Every morphism is applied to the original input size, and outputs are
+/// ignored. This is the same assumption that is made by the bellman-ford
+/// optimizers during path selection.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PassThrough;
+
+impl<Obj, Size: Clone> SizePropagation<Obj, Size> for PassThrough {
+    fn propagate(&self, _target: &Obj, original: &Size, _input: &Size, _output: Size) -> Size {
+        original.clone()
+    }
+}
+
+/// Every object except the first has a constant size that is determined by
+/// the function, regardless of how it was reached.
+#[derive(Clone, Copy, Debug)]
+pub struct ConstantPerObject<F>(pub F);
+
+impl<Obj, Size, F> SizePropagation<Obj, Size> for ConstantPerObject<F>
+where
+    F: Fn(&Obj) -> Size,
+{
+    fn propagate(&self, target: &Obj, _original: &Size, _input: &Size, _output: Size) -> Size {
+        (self.0)(target)
+    }
+}
+
+/// The function combines a morphism's input and output into the size of the
+/// next object.
+#[derive(Clone, Copy, Debug)]
+pub struct Combine<F>(pub F);
+
+impl<Obj, Size, F> SizePropagation<Obj, Size> for Combine<F>
+where
+    F: Fn(&Size, Size) -> Size,
+{
+    fn propagate(&self, _target: &Obj, _original: &Size, input: &Size, output: Size) -> Size {
+        (self.0)(input, output)
+    }
+}
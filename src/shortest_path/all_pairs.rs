@@ -0,0 +1,394 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    marker::PhantomData,
+    ops::Sub,
+};
+
+use crate::{
+    category::{Category, Key, Object},
+    collections::SomeVec,
+    cost::CostMeasure,
+    impls::Float,
+    morphism::{Morphism, MorphismMeta, TryApplyMorphism},
+    propagation::{Accumulate, SizePropagation},
+    vertex::Vertex,
+};
+
+use super::{
+    my_petgraph::PathFindingError,
+    path::{reapply, Path, WellFormedPath},
+};
+
+/// The source index, target index, and cost of a morphism
+type Edge<'a, Id, M, Cost> = (usize, usize, Cost, &'a Morphism<Id, M>);
+
+/// The cheapest path between every pair of objects, found by a single
+/// all-pairs search. Each path is reconstructed on demand with `path`.
+///
+/// Paths are selected in the same way as the Negatable optimizer, where every
+/// morphism is applied to `input_size`. Each reconstructed path is reapplied
+/// with the propagation policy, so its sizes and costs are accumulated. Like
+/// Negatable, every path from a source that can reach a negative cycle is an
+/// error, and the paths between the other objects are unaffected.
+pub struct AllPairs<'a, Id, M, Obj = Id, Size = Float, Cost = Float>
+where
+    M: MorphismMeta,
+{
+    category: &'a Category<Id, M, Obj>,
+    input_size: Size,
+    index: HashMap<Id, usize>,
+    /// The last morphism of the cheapest path from the object with index `i`
+    /// to the object with index `j`, at `i * n + j`
+    last: Vec<Option<&'a Morphism<Id, M>>>,
+    /// A negative cycle that the object with index `i` can reach
+    cycles: Vec<Option<SomeVec<Morphism<Id, M>>>>,
+    cost: PhantomData<Cost>,
+}
+
+impl<'a, Id, M, Obj, Size, Cost> AllPairs<'a, Id, M, Obj, Size, Cost>
+where
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta,
+    Size: Clone,
+    Cost: CostMeasure,
+{
+    pub fn input_size(&self) -> &Size {
+        &self.input_size
+    }
+
+    /// True if there is a path from source to target. This is false if the
+    /// source can reach a negative cycle, because there is no optimal path.
+    pub fn connects(&self, source: &Id, target: &Id) -> bool {
+        match (self.index.get(source), self.index.get(target)) {
+            (Some(i), Some(j)) => self.last[i * self.index.len() + j].is_some(),
+            _ => false,
+        }
+    }
+
+    /// Reconstructs the cheapest path from source to target, where the output
+    /// of each morphism is the input of the next. Returns None if there is no
+    /// path or the target is the source, and an error if the source can reach
+    /// a negative cycle.
+    #[allow(clippy::type_complexity)]
+    pub fn path<const NON_NEGATIVE: bool>(
+        &self,
+        source: &Id,
+        target: &Id,
+    ) -> Result<Option<WellFormedPath<Id, M, Obj, Size, Cost>>, PathFindingError<Id, M, M::Error>>
+    where
+        M: TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+    {
+        self.path_propagating(source, target, &Accumulate)
+    }
+
+    /// Like `path`, where the input of each morphism after the first is
+    /// determined by the propagation policy.
+    #[allow(clippy::type_complexity)]
+    pub fn path_propagating<const NON_NEGATIVE: bool, P: SizePropagation<Obj, Size>>(
+        &self,
+        source: &Id,
+        target: &Id,
+        propagation: &P,
+    ) -> Result<Option<WellFormedPath<Id, M, Obj, Size, Cost>>, PathFindingError<Id, M, M::Error>>
+    where
+        M: TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+    {
+        let n = self.index.len();
+        let i = self.position(source)?;
+        let mut j = self.position(target)?;
+        if let Some(cycle) = &self.cycles[i] {
+            return Err(PathFindingError::NegativeCycle(cycle.clone()));
+        }
+        if i == j || self.last[i * n + j].is_none() {
+            return Ok(None);
+        }
+        let mut morphisms = vec![];
+        while j != i {
+            let morphism = self.last[i * n + j].expect("every object on the path is reachable");
+            j = self.index[&morphism.source];
+            morphisms.push(morphism);
+        }
+        morphisms.reverse();
+
+        let mut vertices = vec![Vertex::Object {
+            inner: self.category.get_object(source).unwrap().clone(),
+            size: self.input_size.clone(),
+        }];
+        for morphism in morphisms {
+            vertices.push(Vertex::Morphism {
+                inner: morphism.clone(),
+                input: self.input_size.clone(),
+                cost: (),
+            });
+            vertices.push(Vertex::Object {
+                inner: self.category.get_object(&morphism.target).unwrap().clone(),
+                size: self.input_size.clone(),
+            });
+        }
+        let (vertices, cost) = reapply(vertices, self.input_size.clone(), propagation)?;
+
+        Ok(Some(WellFormedPath(Path {
+            vertices: vertices.try_into().expect("contains at least one morphism"),
+            cost,
+        })))
+    }
+
+    fn position<E>(&self, id: &Id) -> Result<usize, PathFindingError<Id, M, E>> {
+        self.index
+            .get(id)
+            .copied()
+            .ok_or(PathFindingError::MissingObject(id.clone()))
+    }
+}
+
+/// Finds the cheapest path between every pair of objects with floyd-warshall.
+/// Objects that can reach a negative cycle are left out of the search.
+#[allow(clippy::type_complexity)]
+pub fn all_pairs_with_floyd_warshall<
+    'a,
+    const NON_NEGATIVE: bool,
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+    Size: Clone,
+    Cost: CostMeasure,
+>(
+    category: &'a Category<Id, M, Obj>,
+    input_size: Size,
+    allows: &impl Fn(&Morphism<Id, M>) -> bool,
+) -> AllPairs<'a, Id, M, Obj, Size, Cost> {
+    let (index, edges) = edges(category, &input_size, allows);
+    let n = index.len();
+    let (cycles, edges) = without_negative_cycles(n, edges);
+    let mut distances = vec![None; n * n];
+    let mut last = vec![None; n * n];
+    for i in 0..n {
+        distances[i * n + i] = Some(Cost::zero());
+    }
+    for (i, j, cost, morphism) in edges {
+        if distances[i * n + j].is_none_or(|known| cost < known) {
+            distances[i * n + j] = Some(cost);
+            last[i * n + j] = Some(morphism);
+        }
+    }
+    for k in 0..n {
+        for i in 0..n {
+            let Some(to_k) = distances[i * n + k] else {
+                continue;
+            };
+            for j in 0..n {
+                let Some(from_k) = distances[k * n + j] else {
+                    continue;
+                };
+                let distance = to_k.plus(from_k);
+                if distances[i * n + j].is_none_or(|known| distance < known) {
+                    distances[i * n + j] = Some(distance);
+                    last[i * n + j] = last[k * n + j];
+                }
+            }
+        }
+    }
+
+    AllPairs {
+        category,
+        input_size,
+        index,
+        last,
+        cycles,
+        cost: PhantomData,
+    }
+}
+
+/// Finds the cheapest path between every pair of objects with johnson's
+/// algorithm. Bellman-ford finds a potential for each object that makes every
+/// cost non-negative, and then dijkstra runs from every object with the
+/// adjusted costs. Objects that can reach a negative cycle are left out of the
+/// search. A morphism from an object to itself is never part of a cheapest
+/// path once the negative cycles are removed, so it is dropped.
+#[allow(clippy::type_complexity)]
+pub fn all_pairs_with_johnson<
+    'a,
+    const NON_NEGATIVE: bool,
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+    Size: Clone,
+    Cost: CostMeasure + Sub<Output = Cost>,
+>(
+    category: &'a Category<Id, M, Obj>,
+    input_size: Size,
+    allows: &impl Fn(&Morphism<Id, M>) -> bool,
+) -> AllPairs<'a, Id, M, Obj, Size, Cost> {
+    let (index, edges) = edges(category, &input_size, allows);
+    let n = index.len();
+    let (cycles, edges) = without_negative_cycles(n, edges);
+    // starting every potential at zero is equivalent to searching from a
+    // virtual source with a free edge to every object.
+    let mut potentials = vec![Cost::zero(); n];
+    relax_all(&mut potentials, &edges);
+
+    let mut outbound = vec![vec![]; n];
+    for (i, j, cost, morphism) in edges {
+        if i != j {
+            outbound[i].push((j, cost.plus(potentials[i]).minus(potentials[j]), morphism));
+        }
+    }
+    let mut last = vec![None; n * n];
+    for source in 0..n {
+        let mut settled = vec![false; n];
+        let mut queue = BinaryHeap::from([Reverse((Cost::zero(), source))]);
+        let mut distances = vec![None; n];
+        distances[source] = Some(Cost::zero());
+        while let Some(Reverse((distance, i))) = queue.pop() {
+            if settled[i] {
+                continue;
+            }
+            settled[i] = true;
+            for &(j, cost, morphism) in &outbound[i] {
+                let next = distance.plus(cost);
+                if !settled[j] && distances[j].is_none_or(|known| next < known) {
+                    distances[j] = Some(next);
+                    last[source * n + j] = Some(morphism);
+                    queue.push(Reverse((next, j)));
+                }
+            }
+        }
+    }
+
+    AllPairs {
+        category,
+        input_size,
+        index,
+        last,
+        cycles,
+        cost: PhantomData,
+    }
+}
+
+/// Assigns an index to each object, and returns the source index, target
+/// index, and cost of every allowed morphism that can be applied to
+/// `input_size`.
+#[allow(clippy::type_complexity)]
+fn edges<'a, const NON_NEGATIVE: bool, Id, Obj, M, Size, Cost>(
+    category: &'a Category<Id, M, Obj>,
+    input_size: &Size,
+    allows: &impl Fn(&Morphism<Id, M>) -> bool,
+) -> (HashMap<Id, usize>, Vec<Edge<'a, Id, M, Cost>>)
+where
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+    Size: Clone,
+{
+    let index = category
+        .objects()
+        .enumerate()
+        .map(|(i, object)| (object.id(), i))
+        .collect::<HashMap<_, _>>();
+    let edges = category
+        .morphisms()
+        .filter(|morphism| allows(morphism))
+        .filter_map(|morphism| {
+            let output = morphism
+                .metadata
+                .try_apply(
+                    category.get_object(&morphism.source).unwrap(),
+                    category.get_object(&morphism.target).unwrap(),
+                    input_size.clone(),
+                )
+                .ok()?;
+            Some((
+                index[&morphism.source],
+                index[&morphism.target],
+                output.cost,
+                morphism,
+            ))
+        })
+        .collect();
+    (index, edges)
+}
+
+/// Finds a negative cycle for every object that can reach one, and removes
+/// the morphisms leaving those objects. The remaining morphisms contain no
+/// negative cycle.
+#[allow(clippy::type_complexity)]
+fn without_negative_cycles<'a, Id: Clone, M: Clone, Cost: CostMeasure>(
+    n: usize,
+    mut edges: Vec<Edge<'a, Id, M, Cost>>,
+) -> (
+    Vec<Option<SomeVec<Morphism<Id, M>>>>,
+    Vec<Edge<'a, Id, M, Cost>>,
+) {
+    let mut cycles = vec![None; n];
+    while let Some(cycle) = find_negative_cycle(n, &edges) {
+        // every object that can reach the cycle, found by walking backwards
+        let mut stack = vec![cycle.first().0];
+        while let Some(j) = stack.pop() {
+            if cycles[j].is_none() {
+                cycles[j] = Some(cycle.clone());
+                stack.extend(edges.iter().filter(|edge| edge.1 == j).map(|edge| edge.0));
+            }
+        }
+        edges.retain(|edge| cycles[edge.0].is_none());
+    }
+    let cycles = cycles
+        .into_iter()
+        .map(|cycle| {
+            cycle.map(|cycle: SomeVec<Edge<'a, Id, M, Cost>>| {
+                cycle
+                    .iter()
+                    .map(|edge| edge.3.clone())
+                    .collect::<Vec<_>>()
+                    .try_into()
+                    .expect("every cycle contains a morphism")
+            })
+        })
+        .collect();
+    (cycles, edges)
+}
+
+/// Relaxes every edge until the distances stop improving, and returns the
+/// edge that last improved each object. If the distances still improve after
+/// a round for every object, there is a negative cycle and the last object
+/// that improved is also returned.
+fn relax_all<Id, M, Cost: CostMeasure>(
+    distances: &mut [Cost],
+    edges: &[Edge<'_, Id, M, Cost>],
+) -> (Vec<Option<usize>>, Option<usize>) {
+    let mut predecessors = vec![None; distances.len()];
+    let mut relaxed = None;
+    for _ in 0..=distances.len() {
+        relaxed = None;
+        for (e, &(i, j, cost, _)) in edges.iter().enumerate() {
+            if distances[i].plus(cost) < distances[j] {
+                distances[j] = distances[i].plus(cost);
+                predecessors[j] = Some(e);
+                relaxed = Some(j);
+            }
+        }
+        if relaxed.is_none() {
+            break;
+        }
+    }
+    (predecessors, relaxed)
+}
+
+/// Finds a negative cycle with bellman-ford, where every object starts at
+/// zero. The edges of the cycle are returned in order.
+fn find_negative_cycle<'a, Id, M, Cost: CostMeasure>(
+    n: usize,
+    edges: &[Edge<'a, Id, M, Cost>],
+) -> Option<SomeVec<Edge<'a, Id, M, Cost>>> {
+    let (predecessors, relaxed) = relax_all(&mut vec![Cost::zero(); n], edges);
+    let predecessor = |j: usize| edges[predecessors[j].expect("every object on the walk improved")];
+    // walking back through every object is guaranteed to land in a cycle
+    let start = (0..n).fold(relaxed?, |j, _| predecessor(j).0);
+    let mut cycle = vec![predecessor(start)];
+    while cycle.last().unwrap().0 != start {
+        cycle.push(predecessor(cycle.last().unwrap().0));
+    }
+    cycle.reverse();
+    Some(cycle.try_into().expect("every cycle contains a morphism"))
+}
@@ -0,0 +1,199 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+};
+
+use crate::{
+    category::{Category, Key, Object},
+    cost::CostMeasure,
+    morphism::{InputIndependent, Morphism, MorphismMeta, TryApplyMorphism},
+    propagation::SizePropagation,
+    vertex::Vertex,
+};
+
+use super::{
+    my_petgraph::PathFindingError,
+    path::{reapply, Path, WellFormedPath},
+};
+
+/// One direction of a bidirectional search.
+struct Search<'a, Id, M, Cost> {
+    /// The best known cost between the start and each object, and the
+    /// morphism that connects the object to the rest of that path
+    #[allow(clippy::type_complexity)]
+    best: HashMap<Id, (Cost, Option<&'a Morphism<Id, M>>)>,
+    settled: HashSet<Id>,
+    /// The index of each object in `pushed`, prioritized by its cost
+    queue: BinaryHeap<Reverse<(Cost, usize)>>,
+    pushed: Vec<Id>,
+}
+
+impl<'a, Id: Key, M, Cost: CostMeasure> Search<'a, Id, M, Cost> {
+    fn new(start: Id) -> Self {
+        Self {
+            best: HashMap::from([(start.clone(), (Cost::zero(), None))]),
+            settled: HashSet::new(),
+            queue: BinaryHeap::from([Reverse((Cost::zero(), 0))]),
+            pushed: vec![start],
+        }
+    }
+
+    /// The cost of the next object to settle, or None if there are none left.
+    fn peek(&mut self) -> Option<Cost> {
+        while let Some(Reverse((cost, i))) = self.queue.peek() {
+            if !self.settled.contains(&self.pushed[*i]) {
+                return Some(*cost);
+            }
+            self.queue.pop();
+        }
+        None
+    }
+
+    fn settle(&mut self) -> Option<(Id, Cost)> {
+        self.peek()?;
+        let Reverse((cost, i)) = self.queue.pop()?;
+        let id = self.pushed[i].clone();
+        self.settled.insert(id.clone());
+        Some((id, cost))
+    }
+
+    /// Returns true if this is the cheapest known way to connect the object.
+    fn relax(&mut self, id: &Id, cost: Cost, morphism: &'a Morphism<Id, M>) -> bool {
+        if self.settled.contains(id) || self.best.get(id).is_some_and(|(known, _)| *known <= cost) {
+            return false;
+        }
+        self.best.insert(id.clone(), (cost, Some(morphism)));
+        self.queue.push(Reverse((cost, self.pushed.len())));
+        self.pushed.push(id.clone());
+        true
+    }
+}
+
+/// Finds the cheapest path from source to target with two dijkstra searches,
+/// forwards from the source through outbound morphisms and backwards from the
+/// target through inbound morphisms, which stop once they meet in the middle.
+///
+/// Every morphism is applied to `input_size` to determine its cost, which is
+/// only accurate because the cost does not depend on the input. The path is
+/// then reapplied with the propagation policy to determine its sizes, so an
+/// error is returned if one of its morphisms cannot be applied to its
+/// propagated size.
+#[allow(clippy::type_complexity)]
+pub fn shortest_single_path_with_bidirectional_dijkstra<
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, true> + InputIndependent,
+    Size: Clone,
+    Cost: CostMeasure,
+    P: SizePropagation<Obj, Size>,
+>(
+    category: &Category<Id, M, Obj>,
+    source: Id,
+    target: Id,
+    input_size: Size,
+    propagation: &P,
+    allows: &impl Fn(&Morphism<Id, M>) -> bool,
+) -> Result<Option<WellFormedPath<Id, M, Obj, Size, Cost>>, PathFindingError<Id, M, M::Error>> {
+    if source == target
+        || category.get_object(&source).is_none()
+        || category.get_object(&target).is_none()
+    {
+        return Ok(None);
+    }
+    let cost_of = |morphism: &Morphism<Id, M>| {
+        morphism
+            .metadata
+            .try_apply(
+                category.get_object(&morphism.source).unwrap(),
+                category.get_object(&morphism.target).unwrap(),
+                input_size.clone(),
+            )
+            .ok()
+            .map(|output| output.cost)
+    };
+    let mut forward: Search<Id, M, Cost> = Search::new(source.clone());
+    let mut backward: Search<Id, M, Cost> = Search::new(target.clone());
+    // the cheapest complete path so far, and the object where its two halves
+    // meet
+    let mut best: Option<(Cost, Id)> = None;
+
+    while let (Some(next_forward), Some(next_backward)) = (forward.peek(), backward.peek()) {
+        // any path that has not been found yet costs at least this much
+        if best
+            .as_ref()
+            .is_some_and(|(cost, _)| next_forward.plus(next_backward) >= *cost)
+        {
+            break;
+        }
+        let is_forward = next_forward <= next_backward;
+        let (search, other) = if is_forward {
+            (&mut forward, &backward)
+        } else {
+            (&mut backward, &forward)
+        };
+        let (id, cost) = search.settle().expect("peek found an object");
+        let morphisms: Vec<_> = if is_forward {
+            category.get_outbound(&id).into_iter().flatten().collect()
+        } else {
+            category.get_inbound(&id).into_iter().flatten().collect()
+        };
+        for morphism in morphisms.into_iter().filter(|m| allows(m)) {
+            let next = if is_forward {
+                &morphism.target
+            } else {
+                &morphism.source
+            };
+            let Some(step) = cost_of(morphism) else {
+                continue;
+            };
+            let total = cost.plus(step);
+            if !search.relax(next, total, morphism) {
+                continue;
+            }
+            if let Some((remaining, _)) = other.best.get(next) {
+                let complete = total.plus(*remaining);
+                if best.as_ref().is_none_or(|(cost, _)| complete < *cost) {
+                    best = Some((complete, next.clone()));
+                }
+            }
+        }
+    }
+
+    let Some((_, meeting)) = best else {
+        return Ok(None);
+    };
+    let mut morphisms = vec![];
+    let mut current = meeting.clone();
+    while let Some((_, Some(morphism))) = forward.best.get(&current) {
+        morphisms.push((*morphism).clone());
+        current = morphism.source.clone();
+    }
+    morphisms.reverse();
+    let mut current = meeting;
+    while let Some((_, Some(morphism))) = backward.best.get(&current) {
+        morphisms.push((*morphism).clone());
+        current = morphism.target.clone();
+    }
+
+    let mut vertices = vec![Vertex::Object {
+        inner: category.get_object(&source).unwrap().clone(),
+        size: input_size.clone(),
+    }];
+    for morphism in morphisms {
+        let target = category.get_object(&morphism.target).unwrap().clone();
+        vertices.push(Vertex::Morphism {
+            inner: morphism,
+            input: input_size.clone(),
+            cost: (),
+        });
+        vertices.push(Vertex::Object {
+            inner: target,
+            size: input_size.clone(),
+        });
+    }
+    let (vertices, cost) = reapply(vertices, input_size, propagation)?;
+    Ok(Some(WellFormedPath(Path {
+        vertices: vertices.try_into().expect("contains at least one morphism"),
+        cost,
+    })))
+}
@@ -0,0 +1,238 @@
+use std::collections::HashSet;
+
+use crate::{
+    category::{Category, Key, Object},
+    cost::CostMeasure,
+    morphism::{KeyOf, Morphism, MorphismMeta, TryApplyMorphism},
+    propagation::SizePropagation,
+    vertex::Vertex,
+};
+
+use super::{
+    my_pathfinding::{settle_objects, SettledNode},
+    path::{Path, WellFormedPath},
+};
+
+/// A path found by the search, before it is converted to a WellFormedPath.
+#[derive(Clone)]
+struct Candidate<Id, M, Size, Cost> {
+    /// The object and size at each step, starting with the source
+    states: Vec<(Id, Size)>,
+    /// Each morphism, and the cost of that morphism alone
+    steps: Vec<(Morphism<Id, M>, Cost)>,
+}
+
+impl<Id: Key, M: MorphismMeta, Size: Clone, Cost: CostMeasure> Candidate<Id, M, Size, Cost> {
+    fn cost(&self) -> Cost {
+        Cost::sum(self.steps.iter().map(|(_, cost)| *cost))
+    }
+
+    fn keys(&self) -> Vec<KeyOf<Id, M>> {
+        self.steps.iter().map(|(m, _)| m.key()).collect()
+    }
+
+    /// The first `morphisms` steps of this path, followed by the spur, which
+    /// starts where they end.
+    fn join(&self, morphisms: usize, spur: Self) -> Self {
+        let mut states = self.states[..morphisms].to_vec();
+        states.extend(spur.states);
+        let mut steps = self.steps[..morphisms].to_vec();
+        steps.extend(spur.steps);
+        Self { states, steps }
+    }
+
+    fn into_path<Obj: Object<Id>>(
+        self,
+        category: &Category<Id, M, Obj>,
+    ) -> WellFormedPath<Id, M, Obj, Size, Cost> {
+        let cost = self.cost();
+        let mut vertices = vec![];
+        let mut states = self.states.into_iter();
+        let (id, size) = states.next().expect("contains at least the source");
+        vertices.push(Vertex::Object {
+            inner: category.get_object(&id).unwrap().clone(),
+            size: size.clone(),
+        });
+        let mut input = size;
+        for ((morphism, cost), (id, size)) in self.steps.into_iter().zip(states) {
+            vertices.push(Vertex::Morphism {
+                inner: morphism,
+                input,
+                cost,
+            });
+            vertices.push(Vertex::Object {
+                inner: category.get_object(&id).unwrap().clone(),
+                size: size.clone(),
+            });
+            input = size;
+        }
+        WellFormedPath(Path {
+            vertices: vertices.try_into().expect("contains at least the source"),
+            cost,
+        })
+    }
+}
+
+/// Finds the `k` cheapest paths from source to target that never revisit an
+/// object, using yen's algorithm over (object, size) states.
+///
+/// Each path after the first deviates from an earlier path at some object. The
+/// deviation is found with a fresh dijkstra search that starts from the size
+/// at that object, and which excludes the objects before it and the morphisms
+/// that earlier paths with the same prefix used to leave it. Within a search,
+/// an object is settled at the first size that reaches it, in the same way as
+/// the Accumulating optimizer.
+///
+/// Returns fewer than `k` paths if there are not that many. Costs must not be
+/// negative for the result to be accurate.
+pub fn k_shortest_paths<
+    const NON_NEGATIVE: bool,
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+    Size: Clone,
+    Cost: CostMeasure,
+    P: SizePropagation<Obj, Size>,
+>(
+    category: &Category<Id, M, Obj>,
+    source: Id,
+    target: Id,
+    input_size: Size,
+    k: usize,
+    propagation: &P,
+    allows: &impl Fn(&Morphism<Id, M>) -> bool,
+) -> Vec<WellFormedPath<Id, M, Obj, Size, Cost>> {
+    if k == 0
+        || source == target
+        || category.get_object(&source).is_none()
+        || category.get_object(&target).is_none()
+    {
+        return vec![];
+    }
+    let search = |start: (Id, Size),
+                  excluded_objects: &HashSet<Id>,
+                  excluded_morphisms: &HashSet<KeyOf<Id, M>>| {
+        dijkstra(
+            category,
+            start,
+            &target,
+            excluded_objects,
+            excluded_morphisms,
+            &input_size,
+            propagation,
+            allows,
+        )
+    };
+    let Some(first) = search(
+        (source, input_size.clone()),
+        &HashSet::new(),
+        &HashSet::new(),
+    ) else {
+        return vec![];
+    };
+    let mut seen = HashSet::from([first.keys()]);
+    let mut found = vec![first];
+    let mut candidates: Vec<Candidate<Id, M, Size, Cost>> = vec![];
+
+    while found.len() < k {
+        let previous = found.last().unwrap();
+        for i in 0..previous.steps.len() {
+            let root = &previous.steps[..i];
+            let excluded_morphisms = found
+                .iter()
+                .filter(|path| {
+                    path.steps.len() > i
+                        && path.steps[..i]
+                            .iter()
+                            .map(|(m, _)| m)
+                            .eq(root.iter().map(|(m, _)| m))
+                })
+                .map(|path| path.steps[i].0.key())
+                .collect();
+            let excluded_objects = previous.states[..i]
+                .iter()
+                .map(|(id, _)| id.clone())
+                .collect();
+            let Some(spur) = search(
+                previous.states[i].clone(),
+                &excluded_objects,
+                &excluded_morphisms,
+            ) else {
+                continue;
+            };
+            let candidate = previous.join(i, spur);
+            if seen.insert(candidate.keys()) {
+                candidates.push(candidate);
+            }
+        }
+        let Some(best) = (0..candidates.len()).min_by_key(|&c| candidates[c].cost()) else {
+            break;
+        };
+        found.push(candidates.swap_remove(best));
+    }
+
+    let mut ret = found
+        .into_iter()
+        .map(|candidate| candidate.into_path(category))
+        .collect::<Vec<_>>();
+    // dijkstra only finds each deviation accurately if costs are not negative.
+    // this sort ensures that the result is sorted even when they are.
+    ret.sort_by_key(|wfp| wfp.cost);
+    ret
+}
+
+/// Finds the cheapest path from the start to the target that avoids the
+/// excluded objects, and does not leave the start with an excluded morphism.
+/// Every object is settled once, so the path never revisits an object.
+#[allow(clippy::too_many_arguments)]
+fn dijkstra<const NON_NEGATIVE: bool, Id, Obj, M, Size, Cost, P>(
+    category: &Category<Id, M, Obj>,
+    start: (Id, Size),
+    target: &Id,
+    excluded_objects: &HashSet<Id>,
+    excluded_morphisms: &HashSet<KeyOf<Id, M>>,
+    original: &Size,
+    propagation: &P,
+    allows: &impl Fn(&Morphism<Id, M>) -> bool,
+) -> Option<Candidate<Id, M, Size, Cost>>
+where
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+    Size: Clone,
+    Cost: CostMeasure,
+    P: SizePropagation<Obj, Size>,
+{
+    let settled = settle_objects(
+        category,
+        start,
+        Some(target),
+        excluded_objects,
+        excluded_morphisms,
+        original,
+        propagation,
+        allows,
+    );
+    let node = *settled.objects.get(target)?;
+    Some(trace(settled.nodes, node))
+}
+
+fn trace<Id: Clone, M: Clone, Size: Clone, Cost: Copy>(
+    nodes: Vec<SettledNode<Id, M, Size, Cost>>,
+    node: usize,
+) -> Candidate<Id, M, Size, Cost> {
+    let mut states = vec![];
+    let mut steps = vec![];
+    let mut current = Some(node);
+    while let Some(node) = current {
+        let (state, predecessor) = &nodes[node];
+        states.push(state.clone());
+        current = predecessor.as_ref().map(|(previous, _, _)| *previous);
+        if let Some((_, morphism, cost)) = predecessor {
+            steps.push((morphism.clone(), *cost));
+        }
+    }
+    states.reverse();
+    steps.reverse();
+    Candidate { states, steps }
+}
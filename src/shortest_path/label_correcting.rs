@@ -0,0 +1,199 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::{
+    category::{Category, Key, Object},
+    cost::CostMeasure,
+    morphism::{Morphism, MorphismMeta, TryApplyMorphism},
+    propagation::SizePropagation,
+    vertex::Vertex,
+};
+
+use super::{
+    my_pathfinding::PathfindingSize,
+    my_petgraph::PathFindingError,
+    path::{Path, WellFormedPath},
+};
+
+/// One way to reach an (object, size) state. A label never changes after it
+/// is created, so its cost is always the sum of the costs of its morphisms.
+struct Label<Id, M, Cost> {
+    /// The index of the (object, size) state
+    state: usize,
+    cost: Cost,
+    hops: usize,
+    /// The previous label, the morphism that leads from it, and the cost of
+    /// that morphism alone
+    predecessor: Option<(usize, Morphism<Id, M>, Cost)>,
+    /// Another label of the same state has no more hops and no more cost
+    dominated: bool,
+}
+
+/// Finds the cheapest path from source to target by expanding (object, size)
+/// states with accumulation. A state may be reached by several labels, and a
+/// label is only discarded if another label of the same state has no more
+/// hops and no more cost. Any state may be revisited whenever a cheaper way to
+/// reach it is found, so negative costs are handled correctly, and a cheaper
+/// label with more hops never replaces one that is needed to stay within
+/// `max_hops`.
+///
+/// Paths with more than `max_hops` morphisms are not explored, and an error is
+/// returned if more than `max_states` distinct states are discovered. These
+/// bounds guarantee termination when accumulation produces an unbounded
+/// number of sizes.
+///
+/// Returns an error if a negative cycle of states is found, because that means
+/// there is no optimal path. A morphism that fails to apply to the size of a
+/// state is not expanded from that state, like in every other optimizer, so
+/// the path only uses morphisms that succeeded.
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+pub fn shortest_single_path_with_label_correcting<
+    const NON_NEGATIVE: bool,
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+    Size: PathfindingSize,
+    Cost: CostMeasure,
+    P: SizePropagation<Obj, Size>,
+>(
+    category: &Category<Id, M, Obj>,
+    source: Id,
+    target: Id,
+    input_size: Size,
+    max_hops: usize,
+    max_states: usize,
+    propagation: &P,
+    allows: &impl Fn(&Morphism<Id, M>) -> bool,
+) -> Result<Option<WellFormedPath<Id, M, Obj, Size, Cost>>, PathFindingError<Id, M, M::Error>> {
+    if source == target
+        || category.get_object(&source).is_none()
+        || category.get_object(&target).is_none()
+    {
+        return Ok(None);
+    }
+    let mut states = vec![(source.clone(), input_size.clone())];
+    let mut state_index = HashMap::from([((source, input_size.clone()), 0)]);
+    // the labels of each state that are not dominated
+    let mut frontiers = vec![vec![0]];
+    let mut labels = vec![Label::<Id, M, Cost> {
+        state: 0,
+        cost: Cost::zero(),
+        hops: 0,
+        predecessor: None,
+        dominated: false,
+    }];
+    let mut queue = VecDeque::from([0]);
+
+    while let Some(current) = queue.pop_front() {
+        if labels[current].dominated || labels[current].hops >= max_hops {
+            continue;
+        }
+        let (id, size) = states[labels[current].state].clone();
+        for morphism in category
+            .get_outbound(&id)
+            .into_iter()
+            .flatten()
+            .filter(|m| allows(m))
+        {
+            let Some((next_id, next_size, step_cost)) =
+                morphism.successor(category, size.clone(), &input_size, propagation)
+            else {
+                continue;
+            };
+            let cost = labels[current].cost.plus(step_cost);
+            let hops = labels[current].hops + 1;
+            let state = match state_index.get(&(next_id.clone(), next_size.clone())) {
+                Some(&state) => state,
+                None => {
+                    if states.len() >= max_states {
+                        return Err(PathFindingError::StateBudgetExceeded(max_states));
+                    }
+                    states.push((next_id.clone(), next_size.clone()));
+                    state_index.insert((next_id, next_size), states.len() - 1);
+                    frontiers.push(vec![]);
+                    states.len() - 1
+                }
+            };
+            if frontiers[state]
+                .iter()
+                .any(|&other| labels[other].hops <= hops && labels[other].cost <= cost)
+            {
+                continue;
+            }
+            // an earlier label of the same state on this path would dominate
+            // this one unless the loop between them has a negative cost
+            if let Some(mut cycle) = cycle_to_state(&labels, current, state) {
+                cycle.push(morphism.clone());
+                return Err(PathFindingError::NegativeCycle(
+                    cycle.try_into().expect("just pushed"),
+                ));
+            }
+            let next = labels.len();
+            frontiers[state].retain(|&other| {
+                let dominated = labels[other].cost >= cost && labels[other].hops >= hops;
+                labels[other].dominated |= dominated;
+                !dominated
+            });
+            frontiers[state].push(next);
+            labels.push(Label {
+                state,
+                cost,
+                hops,
+                predecessor: Some((current, morphism.clone(), step_cost)),
+                dominated: false,
+            });
+            queue.push_back(next);
+        }
+    }
+
+    let Some(best) = (0..labels.len())
+        .filter(|&i| states[labels[i].state].0 == target)
+        .min_by_key(|&i| labels[i].cost)
+    else {
+        return Ok(None);
+    };
+    let cost = labels[best].cost;
+    let mut vertices = vec![];
+    let mut current = best;
+    loop {
+        let (id, size) = states[labels[current].state].clone();
+        vertices.push(Vertex::Object {
+            inner: category.get_object(&id).unwrap().clone(),
+            size,
+        });
+        let Some((previous, morphism, cost)) = labels[current].predecessor.clone() else {
+            break;
+        };
+        vertices.push(Vertex::Morphism {
+            inner: morphism,
+            input: states[labels[previous].state].1.clone(),
+            cost,
+        });
+        current = previous;
+    }
+    vertices.reverse();
+
+    Ok(Some(WellFormedPath(Path {
+        vertices: vertices.try_into().expect("contains at least the source"),
+        cost,
+    })))
+}
+
+/// Walks the predecessors from `label` and returns the morphisms leading from
+/// the latest label of `state` to `label`, or None if `state` is not found.
+fn cycle_to_state<Id: Clone, M: Clone, Cost>(
+    labels: &[Label<Id, M, Cost>],
+    label: usize,
+    state: usize,
+) -> Option<Vec<Morphism<Id, M>>> {
+    let mut morphisms = vec![];
+    let mut current = label;
+    loop {
+        if labels[current].state == state {
+            morphisms.reverse();
+            return Some(morphisms);
+        }
+        let (previous, morphism, _) = labels[current].predecessor.as_ref()?;
+        morphisms.push(morphism.clone());
+        current = *previous;
+    }
+}
@@ -0,0 +1,219 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::{
+    category::{Category, Key, Object},
+    morphism::{Morphism, MorphismMeta, TryApplyMorphism},
+    propagation::SizePropagation,
+    vertex::Vertex,
+};
+
+use super::{
+    my_pathfinding::PathfindingSize,
+    path::{Path, WellFormedPath},
+};
+
+/// The objects on a path, as a bit for the index of each object.
+#[derive(Clone)]
+struct Visited(Vec<u64>);
+
+impl Visited {
+    fn new(objects: usize) -> Self {
+        Self(vec![0; objects.div_ceil(64)])
+    }
+
+    fn insert(&mut self, object: usize) {
+        self.0[object / 64] |= 1 << (object % 64);
+    }
+
+    fn contains(&self, object: usize) -> bool {
+        self.0[object / 64] & (1 << (object % 64)) != 0
+    }
+
+    fn is_subset(&self, other: &Self) -> bool {
+        self.0.iter().zip(&other.0).all(|(a, b)| a & !b == 0)
+    }
+}
+
+/// One way to reach an (object, size) state.
+struct Label<Id, M, Cost, W> {
+    state: usize,
+    weight: W,
+    visited: Visited,
+    /// Set when a later label to the same state dominates this one
+    dominated: bool,
+    /// The previous label, the morphism that leads from it, and the cost of
+    /// that morphism alone
+    predecessor: Option<(usize, Morphism<Id, M>, Cost)>,
+}
+
+/// Every label found by `search`.
+pub(super) struct Labels<Id, M, Size, Cost, W> {
+    states: Vec<(Id, Size)>,
+    state_labels: Vec<Vec<usize>>,
+    labels: Vec<Label<Id, M, Cost, W>>,
+}
+
+impl<Id: Key, M: MorphismMeta, Size: Clone, Cost: Clone, W> Labels<Id, M, Size, Cost, W> {
+    /// The labels of the object that are not dominated by another label with
+    /// the same size.
+    pub fn at<'a>(&'a self, object: &'a Id) -> impl Iterator<Item = usize> + 'a {
+        self.states
+            .iter()
+            .zip(&self.state_labels)
+            .filter(move |((id, _), _)| id == object)
+            .flat_map(|(_, labels)| labels.iter().copied())
+    }
+
+    pub fn weight(&self, label: usize) -> &W {
+        &self.labels[label].weight
+    }
+
+    /// Rebuilds the path that leads to the label.
+    #[allow(clippy::type_complexity)]
+    pub fn path<Obj: Object<Id>>(
+        &self,
+        category: &Category<Id, M, Obj>,
+        label: usize,
+        cost: Cost,
+    ) -> WellFormedPath<Id, M, Obj, Size, Cost> {
+        let mut vertices = vec![];
+        let mut current = label;
+        loop {
+            let (id, size) = self.states[self.labels[current].state].clone();
+            vertices.push(Vertex::Object {
+                inner: category.get_object(&id).unwrap().clone(),
+                size,
+            });
+            let Some((previous, morphism, step_cost)) = self.labels[current].predecessor.clone()
+            else {
+                break;
+            };
+            vertices.push(Vertex::Morphism {
+                inner: morphism,
+                input: self.states[self.labels[previous].state].1.clone(),
+                cost: step_cost,
+            });
+            current = previous;
+        }
+        vertices.reverse();
+
+        WellFormedPath(Path {
+            vertices: vertices.try_into().expect("contains at least the source"),
+            cost,
+        })
+    }
+}
+
+/// Expands (object, size) states from the source with multiple labels per
+/// state, until every label that is not dominated has been extended. `extend`
+/// returns the weight of a label after one more morphism, or None if the
+/// label must not be extended with it.
+///
+/// Paths never revisit an object, which guarantees termination regardless of
+/// the sign of the costs or the number of sizes produced by accumulation. A
+/// label is discarded when another label at the same state has a weight that
+/// weakly dominates it and a path that visits no other objects, because every
+/// extension of the discarded label is then also available to the other one.
+/// Morphisms that are not allowed are never used.
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+pub(super) fn search<const NON_NEGATIVE: bool, Id, Obj, M, Size, Cost, W, P>(
+    category: &Category<Id, M, Obj>,
+    source: Id,
+    target: &Id,
+    input_size: Size,
+    start: W,
+    propagation: &P,
+    allows: impl Fn(&Morphism<Id, M>) -> bool,
+    mut extend: impl FnMut(&W, &Morphism<Id, M>, &Size, &Cost) -> Option<W>,
+    weakly_dominates: impl Fn(&W, &W) -> bool,
+) -> Labels<Id, M, Size, Cost, W>
+where
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+    Size: PathfindingSize,
+    Cost: Clone,
+    P: SizePropagation<Obj, Size>,
+{
+    let objects = category
+        .objects()
+        .enumerate()
+        .map(|(i, object)| (object.id(), i))
+        .collect::<HashMap<_, _>>();
+    let mut visited = Visited::new(objects.len());
+    visited.insert(objects[&source]);
+    let mut result = Labels {
+        states: vec![(source.clone(), input_size.clone())],
+        state_labels: vec![vec![0]],
+        labels: vec![Label {
+            state: 0,
+            weight: start,
+            visited,
+            dominated: false,
+            predecessor: None,
+        }],
+    };
+    let Labels {
+        states,
+        state_labels,
+        labels,
+    } = &mut result;
+    let mut state_index = HashMap::from([((source, input_size.clone()), 0)]);
+    let mut queue = VecDeque::from([0]);
+
+    while let Some(current) = queue.pop_front() {
+        if labels[current].dominated {
+            continue;
+        }
+        let (id, size) = states[labels[current].state].clone();
+        if &id == target {
+            continue;
+        }
+        for morphism in category.get_outbound(&id).into_iter().flatten() {
+            let next_object = objects[&morphism.target];
+            if labels[current].visited.contains(next_object) || !allows(morphism) {
+                continue;
+            }
+            let Some((next_id, next_size, step_cost)) =
+                morphism.successor(category, size.clone(), &input_size, propagation)
+            else {
+                continue;
+            };
+            let Some(weight) = extend(&labels[current].weight, morphism, &size, &step_cost) else {
+                continue;
+            };
+            let mut visited = labels[current].visited.clone();
+            visited.insert(next_object);
+            let state = *state_index
+                .entry((next_id.clone(), next_size.clone()))
+                .or_insert_with(|| {
+                    states.push((next_id, next_size));
+                    state_labels.push(vec![]);
+                    states.len() - 1
+                });
+            if state_labels[state].iter().any(|&other| {
+                weakly_dominates(&labels[other].weight, &weight)
+                    && labels[other].visited.is_subset(&visited)
+            }) {
+                continue;
+            }
+            state_labels[state].retain(|&other| {
+                let label = &mut labels[other];
+                let dominated =
+                    weakly_dominates(&weight, &label.weight) && visited.is_subset(&label.visited);
+                label.dominated |= dominated;
+                !dominated
+            });
+            labels.push(Label {
+                state,
+                weight,
+                visited,
+                dominated: false,
+                predecessor: Some((current, morphism.clone(), step_cost)),
+            });
+            state_labels[state].push(labels.len() - 1);
+            queue.push_back(labels.len() - 1);
+        }
+    }
+    result
+}
@@ -0,0 +1,257 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    category::{Category, Key, Object},
+    collections::SomeVec,
+    cost::CostMeasure,
+    morphism::{Morphism, MorphismMeta, TryApplyMorphism},
+    propagation::SizePropagation,
+    vertex::Vertex,
+};
+
+use super::{
+    my_pathfinding::SettledNode,
+    my_petgraph::PathFindingError,
+    path::{reapply, Path, WellFormedPath},
+};
+
+/// The number of extra times that a cycle is traversed to check that it keeps
+/// increasing the size, before it is reported as unbounded.
+const CYCLE_TRAVERSALS: usize = 64;
+
+/// Finds the path from source to target with the largest output size, by
+/// repeatedly relaxing the largest known size of every object, in the style of
+/// bellman-ford.
+///
+/// This relies on the monotonicity contract of ApplyMorphism: a larger input
+/// never results in a smaller output. The propagation policy must also never
+/// decrease a size when the output increases.
+///
+/// Every improvement is kept as a node that points to the node it was reached
+/// from, so the path can always be traced back to the source. The path may go
+/// around a cycle that increases the size, as long as the cycle is bounded, so
+/// it may visit an object more than once.
+///
+/// Once every simple path has been considered, any size that is still
+/// increasing is caused by a cycle. Returns an error if that cycle can reach
+/// the target and still increases the size after `CYCLE_TRAVERSALS` more
+/// traversals. Otherwise the cycle is bounded and relaxation continues, up to
+/// `CYCLE_TRAVERSALS + 1` rounds per object, after which any cycle that is
+/// still increasing a size that can reach the target is reported.
+#[allow(clippy::type_complexity)]
+pub fn max_output_path<
+    const NON_NEGATIVE: bool,
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+    Size: Clone + Ord,
+    Cost: CostMeasure,
+    P: SizePropagation<Obj, Size>,
+>(
+    category: &Category<Id, M, Obj>,
+    source: Id,
+    target: Id,
+    input_size: Size,
+    propagation: &P,
+    allows: &impl Fn(&Morphism<Id, M>) -> bool,
+) -> Result<Option<WellFormedPath<Id, M, Obj, Size, Cost>>, PathFindingError<Id, M, M::Error>> {
+    if source == target
+        || category.get_object(&source).is_none()
+        || category.get_object(&target).is_none()
+    {
+        return Ok(None);
+    }
+    let rounds = category.objects().len();
+    let max_rounds = rounds * (CYCLE_TRAVERSALS + 1);
+    let mut nodes: Vec<SettledNode<Id, M, Size, Cost>> =
+        vec![((source.clone(), input_size.clone()), None)];
+    // the node with the largest known size of each object
+    let mut best = HashMap::from([(source.clone(), 0)]);
+    for round in 1.. {
+        let mut improved = vec![];
+        for id in best.keys().cloned().collect::<Vec<_>>() {
+            for morphism in category
+                .get_outbound(&id)
+                .into_iter()
+                .flatten()
+                .filter(|m| allows(m))
+            {
+                let node = best[&id];
+                let input = nodes[node].0 .1.clone();
+                let Some((next, size, cost)) =
+                    morphism.successor(category, input, &input_size, propagation)
+                else {
+                    continue;
+                };
+                if best
+                    .get(&next)
+                    .is_none_or(|&known| size > nodes[known].0 .1)
+                {
+                    nodes.push(((next.clone(), size), Some((node, morphism.clone(), cost))));
+                    best.insert(next, nodes.len() - 1);
+                    improved.push(nodes.len() - 1);
+                }
+            }
+        }
+        if improved.is_empty() {
+            break;
+        }
+        if round < rounds {
+            continue;
+        }
+        let mut bounded = false;
+        for node in improved {
+            let Some((cycle, entry)) = cycle_behind(&nodes, node) else {
+                continue;
+            };
+            if !reaches(category, &cycle.first().source, &target, allows) {
+                continue;
+            }
+            if round >= max_rounds
+                || keeps_growing(category, &cycle, entry, &input_size, propagation)
+            {
+                return Err(PathFindingError::UnboundedOutput(cycle));
+            }
+            bounded = true;
+        }
+        // improvements that cannot reach the target do not change the result
+        if !bounded {
+            break;
+        }
+    }
+    let Some(&node) = best.get(&target) else {
+        return Ok(None);
+    };
+
+    let mut morphisms = vec![];
+    let mut current = Some(node);
+    while let Some(node) = current {
+        let (_, predecessor) = &nodes[node];
+        current = predecessor.as_ref().map(|(previous, _, _)| *previous);
+        if let Some((_, morphism, _)) = predecessor {
+            morphisms.push(morphism.clone());
+        }
+    }
+    morphisms.reverse();
+    let mut vertices = vec![Vertex::Object {
+        inner: category.get_object(&source).unwrap().clone(),
+        size: input_size.clone(),
+    }];
+    for morphism in morphisms {
+        let target = category.get_object(&morphism.target).unwrap().clone();
+        vertices.push(Vertex::Morphism {
+            inner: morphism,
+            input: input_size.clone(),
+            cost: (),
+        });
+        vertices.push(Vertex::Object {
+            inner: target,
+            size: input_size.clone(),
+        });
+    }
+    let (vertices, cost) = reapply(vertices, input_size, propagation)?;
+
+    Ok(Some(WellFormedPath(Path {
+        vertices: vertices.try_into().expect("contains at least one morphism"),
+        cost,
+    })))
+}
+
+/// Walks back from the node until an object repeats, and returns the morphisms
+/// between the two visits in the order they would be applied, with the size at
+/// the first visit. Returns None if the walk reaches the source first.
+#[allow(clippy::type_complexity)]
+fn cycle_behind<Id: Key, M: Clone, Size: Clone, Cost>(
+    nodes: &[SettledNode<Id, M, Size, Cost>],
+    node: usize,
+) -> Option<(SomeVec<Morphism<Id, M>>, Size)> {
+    let mut seen: HashMap<Id, usize> = HashMap::new();
+    let mut current = Some(node);
+    while let Some(node) = current {
+        let ((id, size), predecessor) = &nodes[node];
+        if let Some(&later) = seen.get(id) {
+            let mut cycle = vec![];
+            let mut step = later;
+            while step != node {
+                let (_, predecessor) = &nodes[step];
+                let (previous, morphism, _) = predecessor
+                    .as_ref()
+                    .expect("only the source has no predecessor");
+                cycle.push(morphism.clone());
+                step = *previous;
+            }
+            cycle.reverse();
+            let cycle = cycle.try_into().expect("the nodes are different");
+            return Some((cycle, size.clone()));
+        }
+        seen.insert(id.clone(), node);
+        current = predecessor.as_ref().map(|(previous, _, _)| *previous);
+    }
+    None
+}
+
+/// True if applying the cycle `CYCLE_TRAVERSALS` times, starting from the
+/// entry size, increases the size every time.
+fn keeps_growing<
+    const NON_NEGATIVE: bool,
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+    Size: Clone + Ord,
+    Cost,
+    P: SizePropagation<Obj, Size>,
+>(
+    category: &Category<Id, M, Obj>,
+    cycle: &SomeVec<Morphism<Id, M>>,
+    entry: Size,
+    original: &Size,
+    propagation: &P,
+) -> bool {
+    let mut size = entry;
+    for _ in 0..CYCLE_TRAVERSALS {
+        let mut next = size.clone();
+        for morphism in cycle.iter() {
+            let Some((_, output, _)) = morphism.successor::<NON_NEGATIVE, Obj, Size, Cost, P>(
+                category,
+                next,
+                original,
+                propagation,
+            ) else {
+                return false;
+            };
+            next = output;
+        }
+        if next <= size {
+            return false;
+        }
+        size = next;
+    }
+    true
+}
+
+/// True if there is any sequence of allowed morphisms from source to target.
+fn reaches<Id: Key, M: MorphismMeta, Obj>(
+    category: &Category<Id, M, Obj>,
+    source: &Id,
+    target: &Id,
+    allows: &impl Fn(&Morphism<Id, M>) -> bool,
+) -> bool {
+    let mut visited = HashSet::from([source.clone()]);
+    let mut stack = vec![source.clone()];
+    while let Some(id) = stack.pop() {
+        if &id == target {
+            return true;
+        }
+        for morphism in category
+            .get_outbound(&id)
+            .into_iter()
+            .flatten()
+            .filter(|m| allows(m))
+        {
+            if visited.insert(morphism.target.clone()) {
+                stack.push(morphism.target.clone());
+            }
+        }
+    }
+    false
+}
@@ -1,24 +1,41 @@
+pub mod all_pairs;
+mod bidirectional;
+mod k_shortest;
+mod label_correcting;
+mod labeling;
+mod max_output;
 mod my_pathfinding;
 mod my_petgraph;
 pub mod optimizer;
+mod pareto;
 pub mod path;
+pub mod query;
+mod resource_constrained;
+mod split;
+pub mod tree;
 
-use std::convert::Infallible;
-
-use petgraph::algo::FloatMeasure;
+use std::{
+    convert::Infallible,
+    ops::{Add, Div, Sub},
+};
 
 use crate::{
     category::{Category, Key, Object},
-    morphism::{ApplyMorphism, MorphismMeta},
+    cost::{CostMeasure, VectorCost},
+    morphism::{ConsumeResources, InputIndependent, Morphism, MorphismMeta, TryApplyMorphism},
+    propagation::{Accumulate, SizePropagation},
 };
 
 use self::{
-    my_pathfinding::{PathfindingCost, PathfindingSize},
-    my_petgraph::PathFindingError,
+    all_pairs::AllPairs,
+    my_pathfinding::PathfindingSize,
     optimizer::Optimizer,
-    path::WellFormedPath,
+    path::{SplitObjective, SplitRoute, WellFormedPath},
+    tree::ShortestPathTree,
 };
 
+pub use self::my_petgraph::{find_negative_cycles, CompiledCategory, PathFindingError};
+
 /// Shortest path optimizer that uses pathfinding::dijkstra.
 ///
 /// Cost is not allowed to be negative.
@@ -28,73 +45,357 @@ use self::{
 /// morphisms.
 pub struct Accumulating;
 
-impl<M, Size, Cost> Optimizer<M, Size, Cost, true> for Accumulating
-where
-    M: MorphismMeta + ApplyMorphism<Size, Cost, true>,
-    Size: PathfindingSize,
-    Cost: PathfindingCost,
-{
-    type Error<Id: Key, O> = Infallible;
+impl Accumulating {
+    /// Runs a single search from the source, and returns the cheapest path to
+    /// every object that it can reach. The path to each object is the same one
+    /// that `shortest_path` returns for it. Returns None if the source is not
+    /// in the category.
+    pub fn shortest_path_tree<Id, M, Obj, Size, Cost>(
+        &self,
+        category: &Category<Id, M, Obj>,
+        source: Id,
+        input_size: Size,
+    ) -> Option<ShortestPathTree<Id, M, Obj, Size, Cost>>
+    where
+        Id: Key,
+        Obj: Object<Id>,
+        M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, true>,
+        Size: PathfindingSize,
+        Cost: CostMeasure,
+    {
+        self.shortest_path_tree_propagating(category, source, input_size, &Accumulate)
+    }
 
-    fn shortest_path<Id, Obj>(
+    /// Like `shortest_path_tree`, where the input of each morphism after the
+    /// first is determined by the propagation policy.
+    pub fn shortest_path_tree_propagating<Id, M, Obj, Size, Cost, P>(
+        &self,
+        category: &Category<Id, M, Obj>,
+        source: Id,
+        input_size: Size,
+        propagation: &P,
+    ) -> Option<ShortestPathTree<Id, M, Obj, Size, Cost>>
+    where
+        Id: Key,
+        Obj: Object<Id>,
+        M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, true>,
+        Size: PathfindingSize,
+        Cost: CostMeasure,
+        P: SizePropagation<Obj, Size>,
+    {
+        self.shortest_path_tree_filtered(category, source, input_size, propagation, &|_| true)
+    }
+
+    /// Like `shortest_path_tree_propagating`, but a morphism is never used if
+    /// `allows` returns false for it.
+    pub fn shortest_path_tree_filtered<Id, M, Obj, Size, Cost, P>(
         &self,
         category: &Category<Id, M, Obj>,
         source: Id,
-        target: Id,
         input_size: Size,
-    ) -> Result<Option<WellFormedPath<Id, M, Obj, Size, Cost>>, Infallible>
+        propagation: &P,
+        allows: &impl Fn(&Morphism<Id, M>) -> bool,
+    ) -> Option<ShortestPathTree<Id, M, Obj, Size, Cost>>
     where
         Id: Key,
         Obj: Object<Id>,
+        M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, true>,
+        Size: PathfindingSize,
+        Cost: CostMeasure,
+        P: SizePropagation<Obj, Size>,
     {
+        my_pathfinding::shortest_path_tree_with_dijkstra(
+            category,
+            source,
+            input_size,
+            propagation,
+            allows,
+        )
+    }
+}
+
+impl<Id, M, Obj, Size, Cost> Optimizer<Id, M, Obj, Size, Cost, true> for Accumulating
+where
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, true>,
+    Size: PathfindingSize,
+    Cost: CostMeasure,
+{
+    type Error = Infallible;
+
+    fn shortest_path_filtered<P: SizePropagation<Obj, Size>>(
+        &self,
+        category: &Category<Id, M, Obj>,
+        source: Id,
+        target: Id,
+        input_size: Size,
+        propagation: &P,
+        allows: &impl Fn(&Morphism<Id, M>) -> bool,
+    ) -> Result<Option<WellFormedPath<Id, M, Obj, Size, Cost>>, Infallible> {
         Ok(my_pathfinding::shortest_single_path_with_dijkstra(
-            category, source, target, input_size,
+            category,
+            source,
+            target,
+            input_size,
+            propagation,
+            allows,
         ))
     }
+
+    fn shortest_paths(
+        &self,
+        category: &Category<Id, M, Obj>,
+        sources: Vec<(Id, Size)>,
+        targets: Vec<Id>,
+    ) -> Result<Vec<WellFormedPath<Id, M, Obj, Size, Cost>>, Self::Error> {
+        let mut results = vec![];
+        for (source, input) in sources {
+            if let Some(tree) = self.shortest_path_tree(category, source, input) {
+                results.extend(targets.iter().filter_map(|target| tree.path_to(target)));
+            }
+        }
+        Ok(results)
+    }
+}
+
+/// Estimates the remaining cost to reach the target from an object with the
+/// given size. This is used by the AStar optimizer to prioritize the objects
+/// that are most likely to be on the cheapest path.
+///
+/// The estimate must be admissible: it must never be greater than the actual
+/// cost of the cheapest path from the object to the target. A heuristic that
+/// always returns zero is admissible, and makes AStar a dijkstra search over
+/// (object, size) states.
+pub trait Heuristic<Id, Size, Cost> {
+    fn estimate(&self, object: &Id, size: &Size) -> Cost;
+}
+
+impl<Id, Size, Cost, F> Heuristic<Id, Size, Cost> for F
+where
+    F: Fn(&Id, &Size) -> Cost,
+{
+    fn estimate(&self, object: &Id, size: &Size) -> Cost {
+        self(object, size)
+    }
+}
+
+/// Shortest path optimizer that uses pathfinding::astar with a user-provided
+/// Heuristic.
+///
+/// Cost is not allowed to be negative.
+///
+/// Accumulates Size information in the same way as Accumulating. With a good
+/// heuristic, far fewer objects are explored than with Accumulating, which
+/// searches every reachable object that is cheaper than the target. An object
+/// may be expanded at several sizes, so a state that is expanded early because
+/// of a low estimate never blocks a cheaper way through the same object.
+pub struct AStar<H>(pub H);
+
+impl<Id, M, Obj, Size, Cost, H> Optimizer<Id, M, Obj, Size, Cost, true> for AStar<H>
+where
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, true>,
+    Size: PathfindingSize,
+    Cost: CostMeasure,
+    H: Heuristic<Id, Size, Cost>,
+{
+    type Error = Infallible;
+
+    fn shortest_path_filtered<P: SizePropagation<Obj, Size>>(
+        &self,
+        category: &Category<Id, M, Obj>,
+        source: Id,
+        target: Id,
+        input_size: Size,
+        propagation: &P,
+        allows: &impl Fn(&Morphism<Id, M>) -> bool,
+    ) -> Result<Option<WellFormedPath<Id, M, Obj, Size, Cost>>, Infallible> {
+        Ok(my_pathfinding::shortest_single_path_with_astar(
+            category,
+            source,
+            target,
+            input_size,
+            &self.0,
+            propagation,
+            allows,
+        ))
+    }
+}
+
+/// Shortest path optimizer that runs dijkstra forwards from the source and
+/// backwards from the target, through each object's inbound morphisms, until
+/// the two searches meet.
+///
+/// Cost is not allowed to be negative.
+///
+/// A backward search cannot know the size that will reach each object, so this
+/// is only available for morphisms whose cost does not depend on their input,
+/// which is promised by [`InputIndependent`]. Every morphism is searched with
+/// the user-provided "input_size", and the returned path is reapplied with the
+/// propagation policy, so it has the same sizes and costs as Accumulating. If a
+/// morphism breaks that promise and cannot be applied to its propagated size,
+/// an error is returned.
+pub struct Bidirectional;
+
+impl<Id, M, Obj, Size, Cost> Optimizer<Id, M, Obj, Size, Cost, true> for Bidirectional
+where
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, true> + InputIndependent,
+    Size: Clone,
+    Cost: CostMeasure,
+{
+    type Error = PathFindingError<Id, M, M::Error>;
+
+    fn shortest_path_filtered<P: SizePropagation<Obj, Size>>(
+        &self,
+        category: &Category<Id, M, Obj>,
+        source: Id,
+        target: Id,
+        input_size: Size,
+        propagation: &P,
+        allows: &impl Fn(&Morphism<Id, M>) -> bool,
+    ) -> Result<Option<WellFormedPath<Id, M, Obj, Size, Cost>>, Self::Error> {
+        bidirectional::shortest_single_path_with_bidirectional_dijkstra(
+            category,
+            source,
+            target,
+            input_size,
+            propagation,
+            allows,
+        )
+    }
 }
 
 /// Shortest path optimizer that uses petgraph::bellman_ford.
 ///
 /// Cost is allowed to be negative. An error will be returned if a negative
-/// cycle exists, because that means there is no optimal path.
+/// cycle exists, because that means there is no optimal path. The error
+/// contains the morphisms that form the cycle. To search for negative cycles
+/// without a source or target, use [`find_negative_cycles`].
 ///
 /// Every morphism uses the user-provided "input_size" as its input. There is no
 /// accumulation of sizes through a path. No object size or morphism input can
 /// be based on the shape of the graph that came before it.
+///
+/// The returned path is reapplied with the propagation policy, so an error is
+/// returned if one of its morphisms cannot be applied to its propagated size.
+///
+/// Each search builds a graph by applying every morphism. To run many searches
+/// with the same category and input size, build it once with
+/// [`CompiledCategory`].
 pub struct Negatable;
 
-impl<M, Size, Cost, const NON_NEGATIVE: bool> Optimizer<M, Size, Cost, NON_NEGATIVE> for Negatable
-where
-    M: MorphismMeta + ApplyMorphism<Size, Cost, NON_NEGATIVE>,
-    Size: Clone,
-    Cost: FloatMeasure,
-{
-    type Error<Id: Key, O> = PathFindingError<Id>;
+impl Negatable {
+    /// Runs bellman-ford once from the source, and returns the cheapest path
+    /// to every object that it can reach. Returns None if the source is not in
+    /// the category, like `shortest_path`.
+    #[allow(clippy::type_complexity)]
+    pub fn shortest_path_tree<Id, M, Obj, Size, Cost, const NON_NEGATIVE: bool>(
+        &self,
+        category: &Category<Id, M, Obj>,
+        source: Id,
+        input_size: Size,
+    ) -> Result<Option<ShortestPathTree<Id, M, Obj, Size, Cost>>, PathFindingError<Id, M, M::Error>>
+    where
+        Id: Key,
+        Obj: Object<Id>,
+        M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+        Size: Clone,
+        Cost: CostMeasure,
+    {
+        self.shortest_path_tree_propagating(category, source, input_size, &Accumulate)
+    }
 
-    fn shortest_path<Id, Obj>(
+    /// Like `shortest_path_tree`, where the input of each morphism after the
+    /// first is determined by the propagation policy.
+    #[allow(clippy::type_complexity)]
+    pub fn shortest_path_tree_propagating<Id, M, Obj, Size, Cost, P, const NON_NEGATIVE: bool>(
         &self,
         category: &Category<Id, M, Obj>,
         source: Id,
-        target: Id,
         input_size: Size,
-    ) -> Result<Option<WellFormedPath<Id, M, Obj, Size, Cost>>, PathFindingError<Id>>
+        propagation: &P,
+    ) -> Result<Option<ShortestPathTree<Id, M, Obj, Size, Cost>>, PathFindingError<Id, M, M::Error>>
     where
         Id: Key,
         Obj: Object<Id>,
+        M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+        Size: Clone,
+        Cost: CostMeasure,
+        P: SizePropagation<Obj, Size>,
     {
-        my_petgraph::shortest_single_path_with_bellman_ford(category, source, target, input_size)
+        self.shortest_path_tree_filtered(category, source, input_size, propagation, &|_| true)
     }
 
-    fn shortest_paths<Id, Obj>(
+    /// Like `shortest_path_tree_propagating`, but a morphism is never used if
+    /// `allows` returns false for it.
+    #[allow(clippy::type_complexity)]
+    pub fn shortest_path_tree_filtered<Id, M, Obj, Size, Cost, P, const NON_NEGATIVE: bool>(
         &self,
         category: &Category<Id, M, Obj>,
-        sources: Vec<(Id, Size)>,
-        targets: Vec<Id>,
-    ) -> Result<Vec<WellFormedPath<Id, M, Obj, Size, Cost>>, Self::Error<Id, Obj>>
+        source: Id,
+        input_size: Size,
+        propagation: &P,
+        allows: &impl Fn(&Morphism<Id, M>) -> bool,
+    ) -> Result<Option<ShortestPathTree<Id, M, Obj, Size, Cost>>, PathFindingError<Id, M, M::Error>>
     where
         Id: Key,
         Obj: Object<Id>,
+        M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+        Size: Clone,
+        Cost: CostMeasure,
+        P: SizePropagation<Obj, Size>,
+    {
+        my_petgraph::shortest_path_tree_with_bellman_ford(
+            category,
+            source,
+            input_size,
+            propagation,
+            allows,
+        )
+    }
+}
+
+impl<Id, M, Obj, Size, Cost, const NON_NEGATIVE: bool>
+    Optimizer<Id, M, Obj, Size, Cost, NON_NEGATIVE> for Negatable
+where
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+    Size: Clone,
+    Cost: CostMeasure,
+{
+    type Error = PathFindingError<Id, M, M::Error>;
+
+    fn shortest_path_filtered<P: SizePropagation<Obj, Size>>(
+        &self,
+        category: &Category<Id, M, Obj>,
+        source: Id,
+        target: Id,
+        input_size: Size,
+        propagation: &P,
+        allows: &impl Fn(&Morphism<Id, M>) -> bool,
+    ) -> Result<Option<WellFormedPath<Id, M, Obj, Size, Cost>>, PathFindingError<Id, M, M::Error>>
     {
+        my_petgraph::shortest_single_path_with_bellman_ford(
+            category,
+            source,
+            target,
+            input_size,
+            propagation,
+            allows,
+        )
+    }
+
+    fn shortest_paths(
+        &self,
+        category: &Category<Id, M, Obj>,
+        sources: Vec<(Id, Size)>,
+        targets: Vec<Id>,
+    ) -> Result<Vec<WellFormedPath<Id, M, Obj, Size, Cost>>, Self::Error> {
         let mut results = vec![];
         for (source, input) in sources {
             results.extend(my_petgraph::shortest_multi_path_with_bellman_ford(
@@ -102,12 +403,65 @@ where
                 source.clone(),
                 &targets,
                 input.clone(),
+                &Accumulate,
             )?);
         }
         Ok(results)
     }
 }
 
+/// Shortest path optimizer that uses a label-correcting algorithm over
+/// (object, size) states.
+///
+/// Cost is allowed to be negative. An error will be returned if a negative
+/// cycle exists, because that means there is no optimal path.
+///
+/// Accumulates Size information in the same way as Accumulating. Unlike
+/// dijkstra, an object may be revisited whenever a cheaper way to reach it is
+/// found, which is what makes negative costs safe. A more expensive way to
+/// reach a state is still kept if it uses fewer morphisms, so the cheapest
+/// path within `max_hops` is always found. The search is bounded:
+/// - `max_hops`: paths with more morphisms than this are not explored.
+/// - `max_states`: an error is returned if the search discovers more distinct
+///   (object, size) states than this.
+pub struct LabelCorrecting {
+    pub max_hops: usize,
+    pub max_states: usize,
+}
+
+impl<Id, M, Obj, Size, Cost, const NON_NEGATIVE: bool>
+    Optimizer<Id, M, Obj, Size, Cost, NON_NEGATIVE> for LabelCorrecting
+where
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+    Size: PathfindingSize,
+    Cost: CostMeasure,
+{
+    type Error = PathFindingError<Id, M, M::Error>;
+
+    fn shortest_path_filtered<P: SizePropagation<Obj, Size>>(
+        &self,
+        category: &Category<Id, M, Obj>,
+        source: Id,
+        target: Id,
+        input_size: Size,
+        propagation: &P,
+        allows: &impl Fn(&Morphism<Id, M>) -> bool,
+    ) -> Result<Option<WellFormedPath<Id, M, Obj, Size, Cost>>, Self::Error> {
+        label_correcting::shortest_single_path_with_label_correcting(
+            category,
+            source,
+            target,
+            input_size,
+            self.max_hops,
+            self.max_states,
+            propagation,
+            allows,
+        )
+    }
+}
+
 /// Use this if you prefer "good enough" results instead of errors.
 ///
 /// If there are paths from source to target, but none are optimal (due to a
@@ -127,49 +481,52 @@ where
 /// if it is good enough.
 pub struct NegatableInfallible;
 
-impl<M, Size, Cost, const NON_NEGATIVE: bool> Optimizer<M, Size, Cost, NON_NEGATIVE>
-    for NegatableInfallible
+impl<Id, M, Obj, Size, Cost, const NON_NEGATIVE: bool>
+    Optimizer<Id, M, Obj, Size, Cost, NON_NEGATIVE> for NegatableInfallible
 where
-    M: MorphismMeta + ApplyMorphism<Size, Cost, NON_NEGATIVE>,
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
     Size: PathfindingSize + Clone,
-    Cost: PathfindingCost + FloatMeasure,
+    Cost: CostMeasure,
 {
-    type Error<Id: Key, O> = Infallible;
+    type Error = Infallible;
 
-    fn shortest_path<Id, Obj>(
+    fn shortest_path_filtered<P: SizePropagation<Obj, Size>>(
         &self,
         category: &Category<Id, M, Obj>,
         source: Id,
         target: Id,
         input_size: Size,
-    ) -> Result<Option<WellFormedPath<Id, M, Obj, Size, Cost>>, Infallible>
-    where
-        Id: Key,
-        Obj: Object<Id>,
-    {
+        propagation: &P,
+        allows: &impl Fn(&Morphism<Id, M>) -> bool,
+    ) -> Result<Option<WellFormedPath<Id, M, Obj, Size, Cost>>, Infallible> {
         Ok(my_petgraph::shortest_single_path_with_bellman_ford(
             category,
             source.clone(),
             target.clone(),
             input_size.clone(),
+            propagation,
+            allows,
         )
         .unwrap_or_else(|_| {
             my_pathfinding::inaccurate_shortest_single_path_with_dijkstra(
-                category, source, target, input_size,
+                category,
+                source,
+                target,
+                input_size,
+                propagation,
+                allows,
             )
         }))
     }
 
-    fn shortest_paths<Id, Obj>(
+    fn shortest_paths(
         &self,
         category: &Category<Id, M, Obj>,
         sources: Vec<(Id, Size)>,
         targets: Vec<Id>,
-    ) -> Result<Vec<WellFormedPath<Id, M, Obj, Size, Cost>>, Self::Error<Id, Obj>>
-    where
-        Id: Key,
-        Obj: Object<Id>,
-    {
+    ) -> Result<Vec<WellFormedPath<Id, M, Obj, Size, Cost>>, Self::Error> {
         let mut results = vec![];
         for (source, input) in sources {
             match my_petgraph::shortest_multi_path_with_bellman_ford(
@@ -177,6 +534,7 @@ where
                 source.clone(),
                 &targets,
                 input.clone(),
+                &Accumulate,
             ) {
                 Ok(paths) => results.extend(paths),
                 Err(_) => {
@@ -187,6 +545,8 @@ where
                                 source.clone(),
                                 target,
                                 input.clone(),
+                                &Accumulate,
+                                &|_| true,
                             )
                         {
                             results.push(path);
@@ -200,53 +560,717 @@ where
 }
 
 /// Uses dijkstra, which normally cannot handle negative costs accurately. Good
-/// results are achieved by selecting numerous paths and then sorting them by
-/// cost.
+/// results are achieved by selecting numerous paths in the same way as
+/// KShortest and then sorting them by cost.
 pub struct NegatableByRank(pub usize);
 
 impl NegatableByRank {
-    pub fn shortest_path_options<M, Size, Cost, Id, Obj, const NON_NEGATIVE: bool>(
+    pub fn shortest_path_options<M, Size, Cost, Id, Obj, P, const NON_NEGATIVE: bool>(
         &self,
         category: &Category<Id, M, Obj>,
         source: Id,
         target: Id,
         input_size: Size,
+        propagation: &P,
     ) -> Vec<WellFormedPath<Id, M, Obj, Size, Cost>>
     where
-        M: MorphismMeta + ApplyMorphism<Size, Cost, NON_NEGATIVE>,
+        M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
         Size: PathfindingSize + Clone,
-        Cost: PathfindingCost + FloatMeasure,
+        Cost: CostMeasure,
         Id: Key,
         Obj: Object<Id>,
+        P: SizePropagation<Obj, Size>,
     {
-        my_pathfinding::inaccurate_shortest_single_path_with_dijkstra_yen(
-            category, source, target, input_size, self.0,
+        k_shortest::k_shortest_paths(
+            category,
+            source,
+            target,
+            input_size,
+            self.0,
+            propagation,
+            &|_| true,
         )
     }
 }
 
-impl<M, Size, Cost, const NON_NEGATIVE: bool> Optimizer<M, Size, Cost, NON_NEGATIVE>
-    for NegatableByRank
+impl<Id, M, Obj, Size, Cost, const NON_NEGATIVE: bool>
+    Optimizer<Id, M, Obj, Size, Cost, NON_NEGATIVE> for NegatableByRank
 where
-    M: MorphismMeta + ApplyMorphism<Size, Cost, NON_NEGATIVE>,
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
     Size: PathfindingSize + Clone,
-    Cost: PathfindingCost + FloatMeasure,
+    Cost: CostMeasure,
+{
+    type Error = Infallible;
+
+    fn shortest_path_filtered<P: SizePropagation<Obj, Size>>(
+        &self,
+        category: &Category<Id, M, Obj>,
+        source: Id,
+        target: Id,
+        input_size: Size,
+        propagation: &P,
+        allows: &impl Fn(&Morphism<Id, M>) -> bool,
+    ) -> Result<Option<WellFormedPath<Id, M, Obj, Size, Cost>>, Infallible> {
+        Ok(k_shortest::k_shortest_paths(
+            category,
+            source,
+            target,
+            input_size,
+            self.0,
+            propagation,
+            allows,
+        )
+        .into_iter()
+        .next())
+    }
+}
+
+/// Finds the cheapest path between every pair of objects with floyd-warshall.
+/// It takes time cubic in the number of objects, regardless of the number of
+/// morphisms, so it is best for dense categories.
+///
+/// Paths are selected in the same way as Negatable: every morphism uses the
+/// input size, and sizes are only accumulated when a path is reconstructed.
+/// Cost is allowed to be negative. Like Negatable, a path from a source that
+/// can reach a negative cycle is an error, because it has no optimal path.
+pub struct FloydWarshall;
+
+impl FloydWarshall {
+    #[allow(clippy::type_complexity)]
+    pub fn all_pairs<'a, Id, M, Obj, Size, Cost, const NON_NEGATIVE: bool>(
+        &self,
+        category: &'a Category<Id, M, Obj>,
+        input_size: Size,
+    ) -> AllPairs<'a, Id, M, Obj, Size, Cost>
+    where
+        Id: Key,
+        Obj: Object<Id>,
+        M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+        Size: Clone,
+        Cost: CostMeasure,
+    {
+        self.all_pairs_filtered(category, input_size, &|_| true)
+    }
+
+    /// Like `all_pairs`, but a morphism is never used if `allows` returns
+    /// false for it.
+    #[allow(clippy::type_complexity)]
+    pub fn all_pairs_filtered<'a, Id, M, Obj, Size, Cost, const NON_NEGATIVE: bool>(
+        &self,
+        category: &'a Category<Id, M, Obj>,
+        input_size: Size,
+        allows: &impl Fn(&Morphism<Id, M>) -> bool,
+    ) -> AllPairs<'a, Id, M, Obj, Size, Cost>
+    where
+        Id: Key,
+        Obj: Object<Id>,
+        M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+        Size: Clone,
+        Cost: CostMeasure,
+    {
+        all_pairs::all_pairs_with_floyd_warshall(category, input_size, allows)
+    }
+}
+
+/// Finds the cheapest path between every pair of objects with johnson's
+/// algorithm: bellman-ford adjusts the costs so that none are negative, and
+/// then dijkstra runs from every object. It is faster than FloydWarshall when
+/// there are few morphisms per object.
+///
+/// Paths are selected and negative cycles are reported in the same way as
+/// FloydWarshall. The cost must support subtraction to adjust it.
+pub struct Johnson;
+
+impl Johnson {
+    #[allow(clippy::type_complexity)]
+    pub fn all_pairs<'a, Id, M, Obj, Size, Cost, const NON_NEGATIVE: bool>(
+        &self,
+        category: &'a Category<Id, M, Obj>,
+        input_size: Size,
+    ) -> AllPairs<'a, Id, M, Obj, Size, Cost>
+    where
+        Id: Key,
+        Obj: Object<Id>,
+        M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+        Size: Clone,
+        Cost: CostMeasure + Sub<Output = Cost>,
+    {
+        self.all_pairs_filtered(category, input_size, &|_| true)
+    }
+
+    /// Like `all_pairs`, but a morphism is never used if `allows` returns
+    /// false for it.
+    #[allow(clippy::type_complexity)]
+    pub fn all_pairs_filtered<'a, Id, M, Obj, Size, Cost, const NON_NEGATIVE: bool>(
+        &self,
+        category: &'a Category<Id, M, Obj>,
+        input_size: Size,
+        allows: &impl Fn(&Morphism<Id, M>) -> bool,
+    ) -> AllPairs<'a, Id, M, Obj, Size, Cost>
+    where
+        Id: Key,
+        Obj: Object<Id>,
+        M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+        Size: Clone,
+        Cost: CostMeasure + Sub<Output = Cost>,
+    {
+        all_pairs::all_pairs_with_johnson(category, input_size, allows)
+    }
+}
+
+/// Finds the `k` cheapest paths that never revisit an object, with yen's
+/// algorithm over (object, size) states. Every deviation from an earlier path
+/// is found with a separate dijkstra search, so a path is never missed because
+/// another search already visited its objects.
+///
+/// Accumulates Size information in the same way as Accumulating, and has the
+/// same accuracy. As an Optimizer, it returns the cheapest path.
+pub struct KShortest(pub usize);
+
+impl KShortest {
+    /// Returns up to `k` distinct paths from source to target, sorted by cost.
+    pub fn k_shortest_paths<Id, M, Obj, Size, Cost>(
+        &self,
+        category: &Category<Id, M, Obj>,
+        source: Id,
+        target: Id,
+        input_size: Size,
+    ) -> Vec<WellFormedPath<Id, M, Obj, Size, Cost>>
+    where
+        Id: Key,
+        Obj: Object<Id>,
+        M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, true>,
+        Size: Clone,
+        Cost: CostMeasure,
+    {
+        self.k_shortest_paths_propagating(category, source, target, input_size, &Accumulate)
+    }
+
+    /// Like `k_shortest_paths`, where the input of each morphism after the
+    /// first is determined by the propagation policy.
+    pub fn k_shortest_paths_propagating<Id, M, Obj, Size, Cost, P>(
+        &self,
+        category: &Category<Id, M, Obj>,
+        source: Id,
+        target: Id,
+        input_size: Size,
+        propagation: &P,
+    ) -> Vec<WellFormedPath<Id, M, Obj, Size, Cost>>
+    where
+        Id: Key,
+        Obj: Object<Id>,
+        M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, true>,
+        Size: Clone,
+        Cost: CostMeasure,
+        P: SizePropagation<Obj, Size>,
+    {
+        self.k_shortest_paths_filtered(category, source, target, input_size, propagation, &|_| true)
+    }
+
+    /// Like `k_shortest_paths_propagating`, but a morphism is never used if
+    /// `allows` returns false for it.
+    pub fn k_shortest_paths_filtered<Id, M, Obj, Size, Cost, P>(
+        &self,
+        category: &Category<Id, M, Obj>,
+        source: Id,
+        target: Id,
+        input_size: Size,
+        propagation: &P,
+        allows: &impl Fn(&Morphism<Id, M>) -> bool,
+    ) -> Vec<WellFormedPath<Id, M, Obj, Size, Cost>>
+    where
+        Id: Key,
+        Obj: Object<Id>,
+        M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, true>,
+        Size: Clone,
+        Cost: CostMeasure,
+        P: SizePropagation<Obj, Size>,
+    {
+        k_shortest::k_shortest_paths(
+            category,
+            source,
+            target,
+            input_size,
+            self.0,
+            propagation,
+            allows,
+        )
+    }
+}
+
+impl<Id, M, Obj, Size, Cost> Optimizer<Id, M, Obj, Size, Cost, true> for KShortest
+where
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, true>,
+    Size: PathfindingSize,
+    Cost: CostMeasure,
+{
+    type Error = Infallible;
+
+    fn shortest_path_filtered<P: SizePropagation<Obj, Size>>(
+        &self,
+        category: &Category<Id, M, Obj>,
+        source: Id,
+        target: Id,
+        input_size: Size,
+        propagation: &P,
+        allows: &impl Fn(&Morphism<Id, M>) -> bool,
+    ) -> Result<Option<WellFormedPath<Id, M, Obj, Size, Cost>>, Infallible> {
+        Ok(k_shortest::k_shortest_paths(
+            category,
+            source,
+            target,
+            input_size,
+            self.0,
+            propagation,
+            allows,
+        )
+        .into_iter()
+        .next())
+    }
+}
+
+/// Wraps another optimizer so that it never returns a path with more than
+/// `max_morphisms` morphisms. The limit is exact: the result is the best path
+/// within the limit, even if a better path exists with more morphisms.
+///
+/// Supported optimizers:
+/// - Accumulating and NegatableByRank: dijkstra and yen over states that
+///   include the number of morphisms so far.
+/// - Negatable: bellman-ford limited to `max_morphisms` rounds. A negative cycle
+///   is not an error, because there is always an optimal path within the
+///   limit.
+/// - NegatableInfallible: the same as Negatable, falling back to dijkstra if a
+///   morphism in the selected path fails to apply.
+///
+/// When the limit allows it, the returned path may visit an object more than
+/// once if that makes it cheaper.
+pub struct HopLimited<O> {
+    pub optimizer: O,
+    pub max_morphisms: usize,
+}
+
+impl<Id, M, Obj, Size, Cost> Optimizer<Id, M, Obj, Size, Cost, true> for HopLimited<Accumulating>
+where
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, true>,
+    Size: PathfindingSize,
+    Cost: CostMeasure,
+{
+    type Error = Infallible;
+
+    fn shortest_path_filtered<P: SizePropagation<Obj, Size>>(
+        &self,
+        category: &Category<Id, M, Obj>,
+        source: Id,
+        target: Id,
+        input_size: Size,
+        propagation: &P,
+        allows: &impl Fn(&Morphism<Id, M>) -> bool,
+    ) -> Result<Option<WellFormedPath<Id, M, Obj, Size, Cost>>, Infallible> {
+        Ok(my_pathfinding::inaccurate_hop_limited_dijkstra_yen(
+            category,
+            source,
+            target,
+            input_size,
+            self.max_morphisms,
+            1,
+            propagation,
+            allows,
+        )
+        .into_iter()
+        .next())
+    }
+}
+
+impl<Id, M, Obj, Size, Cost, const NON_NEGATIVE: bool>
+    Optimizer<Id, M, Obj, Size, Cost, NON_NEGATIVE> for HopLimited<Negatable>
+where
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+    Size: Clone,
+    Cost: CostMeasure,
+{
+    type Error = PathFindingError<Id, M, M::Error>;
+
+    fn shortest_path_filtered<P: SizePropagation<Obj, Size>>(
+        &self,
+        category: &Category<Id, M, Obj>,
+        source: Id,
+        target: Id,
+        input_size: Size,
+        propagation: &P,
+        allows: &impl Fn(&Morphism<Id, M>) -> bool,
+    ) -> Result<Option<WellFormedPath<Id, M, Obj, Size, Cost>>, PathFindingError<Id, M, M::Error>>
+    {
+        my_petgraph::shortest_single_path_with_hop_limited_bellman_ford(
+            category,
+            source,
+            target,
+            input_size,
+            self.max_morphisms,
+            propagation,
+            allows,
+        )
+    }
+}
+
+impl<Id, M, Obj, Size, Cost, const NON_NEGATIVE: bool>
+    Optimizer<Id, M, Obj, Size, Cost, NON_NEGATIVE> for HopLimited<NegatableInfallible>
+where
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+    Size: PathfindingSize,
+    Cost: CostMeasure,
+{
+    type Error = Infallible;
+
+    fn shortest_path_filtered<P: SizePropagation<Obj, Size>>(
+        &self,
+        category: &Category<Id, M, Obj>,
+        source: Id,
+        target: Id,
+        input_size: Size,
+        propagation: &P,
+        allows: &impl Fn(&Morphism<Id, M>) -> bool,
+    ) -> Result<Option<WellFormedPath<Id, M, Obj, Size, Cost>>, Infallible> {
+        Ok(
+            my_petgraph::shortest_single_path_with_hop_limited_bellman_ford(
+                category,
+                source.clone(),
+                target.clone(),
+                input_size.clone(),
+                self.max_morphisms,
+                propagation,
+                allows,
+            )
+            .unwrap_or_else(|_| {
+                my_pathfinding::inaccurate_hop_limited_dijkstra_yen(
+                    category,
+                    source,
+                    target,
+                    input_size,
+                    self.max_morphisms,
+                    1,
+                    propagation,
+                    allows,
+                )
+                .into_iter()
+                .next()
+            }),
+        )
+    }
+}
+
+impl<Id, M, Obj, Size, Cost, const NON_NEGATIVE: bool>
+    Optimizer<Id, M, Obj, Size, Cost, NON_NEGATIVE> for HopLimited<NegatableByRank>
+where
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+    Size: PathfindingSize,
+    Cost: CostMeasure,
+{
+    type Error = Infallible;
+
+    fn shortest_path_filtered<P: SizePropagation<Obj, Size>>(
+        &self,
+        category: &Category<Id, M, Obj>,
+        source: Id,
+        target: Id,
+        input_size: Size,
+        propagation: &P,
+        allows: &impl Fn(&Morphism<Id, M>) -> bool,
+    ) -> Result<Option<WellFormedPath<Id, M, Obj, Size, Cost>>, Infallible> {
+        Ok(my_pathfinding::inaccurate_hop_limited_dijkstra_yen(
+            category,
+            source,
+            target,
+            input_size,
+            self.max_morphisms,
+            self.optimizer.0,
+            propagation,
+            allows,
+        )
+        .into_iter()
+        .next())
+    }
+}
+
+/// Finds the cheapest path whose total resources are within `limits`. The
+/// resources of each morphism are reported by [`ConsumeResources`], and they
+/// are summed like a [`VectorCost`], so each component has its own limit.
+///
+/// Accumulates Size information in the same way as Accumulating. Paths never
+/// revisit an object. When two paths reach the same object with the same size,
+/// one is only discarded if the other has no greater cost, no greater
+/// resources, and has not visited any other object, so a more expensive path
+/// is kept if it leaves more room within the limits or can still reach more
+/// objects. Costs are allowed to be negative.
+pub struct ResourceConstrained<R> {
+    pub limits: R,
+}
+
+impl<Id, M, Obj, Size, Cost, R, const NON_NEGATIVE: bool>
+    Optimizer<Id, M, Obj, Size, Cost, NON_NEGATIVE> for ResourceConstrained<R>
+where
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta
+        + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>
+        + ConsumeResources<Obj, Size, R>,
+    Size: PathfindingSize,
+    Cost: CostMeasure,
+    R: VectorCost,
 {
-    type Error<Id: Key, O> = Infallible;
+    type Error = Infallible;
 
-    fn shortest_path<Id, Obj>(
+    fn shortest_path_filtered<P: SizePropagation<Obj, Size>>(
         &self,
         category: &Category<Id, M, Obj>,
         source: Id,
         target: Id,
         input_size: Size,
-    ) -> Result<Option<WellFormedPath<Id, M, Obj, Size, Cost>>, Infallible>
+        propagation: &P,
+        allows: &impl Fn(&Morphism<Id, M>) -> bool,
+    ) -> Result<Option<WellFormedPath<Id, M, Obj, Size, Cost>>, Infallible> {
+        Ok(
+            resource_constrained::shortest_single_path_with_resource_limits(
+                category,
+                source,
+                target,
+                input_size,
+                &self.limits,
+                propagation,
+                allows,
+            ),
+        )
+    }
+}
+
+/// Optimizer that selects the path with the largest output size at the target,
+/// rather than the lowest cost. This is usually the objective for swap routing.
+/// The returned path still contains the cost of each morphism and their sum,
+/// and [`optimizer::score::output`] ranks paths by output size.
+///
+/// Accumulates Size information in the same way as Accumulating. Morphisms must
+/// satisfy the monotonicity contract documented on ApplyMorphism, and costs are
+/// ignored during path selection, so they may be negative.
+///
+/// A cycle that increases the size may be part of the path if it is bounded,
+/// for example by liquidity, so the path may visit an object more than once.
+/// An error will be returned if there is a cycle that increases the size
+/// without bound and can reach the target. The error contains the morphisms
+/// that form the cycle.
+///
+/// Whether a cycle is unbounded cannot be decided in general, so this is an
+/// approximation: a cycle is reported if it still increases the size after it
+/// is traversed 64 more times, or if sizes are still increasing after 65
+/// rounds of relaxation per object. A bounded cycle that converges more slowly
+/// than that is reported as unbounded.
+pub struct MaxOutput;
+
+impl<Id, M, Obj, Size, Cost, const NON_NEGATIVE: bool>
+    Optimizer<Id, M, Obj, Size, Cost, NON_NEGATIVE> for MaxOutput
+where
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+    Size: Clone + Ord,
+    Cost: CostMeasure,
+{
+    type Error = PathFindingError<Id, M, M::Error>;
+
+    fn shortest_path_filtered<P: SizePropagation<Obj, Size>>(
+        &self,
+        category: &Category<Id, M, Obj>,
+        source: Id,
+        target: Id,
+        input_size: Size,
+        propagation: &P,
+        allows: &impl Fn(&Morphism<Id, M>) -> bool,
+    ) -> Result<Option<WellFormedPath<Id, M, Obj, Size, Cost>>, PathFindingError<Id, M, M::Error>>
+    {
+        max_output::max_output_path(category, source, target, input_size, propagation, allows)
+    }
+}
+
+/// Finds every path whose cost is not dominated by another path, for costs
+/// with several components that are minimized together. See [`VectorCost`].
+///
+/// This does not implement Optimizer, because there is usually no single best
+/// path.
+///
+/// Accumulates Size information in the same way as Accumulating. Paths never
+/// revisit an object. When two paths reach the same object with the same size,
+/// the one with a dominated cost is not explored any further, unless it can
+/// still reach an object that the other path has already visited, so costs
+/// are allowed to be negative.
+pub struct ParetoOptimizer;
+
+impl ParetoOptimizer {
+    /// Returns the paths from source to target whose costs are not dominated,
+    /// in no particular order. When several paths have the same cost, only one
+    /// of them is returned.
+    pub fn pareto_front<Id, M, Obj, Size, Cost, const NON_NEGATIVE: bool>(
+        &self,
+        category: &Category<Id, M, Obj>,
+        source: Id,
+        target: Id,
+        input_size: Size,
+    ) -> Vec<WellFormedPath<Id, M, Obj, Size, Cost>>
     where
         Id: Key,
         Obj: Object<Id>,
+        M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+        Size: PathfindingSize,
+        Cost: VectorCost,
     {
-        Ok(self
-            .shortest_path_options(category, source, target, input_size)
-            .pop())
+        self.pareto_front_propagating(category, source, target, input_size, &Accumulate)
+    }
+
+    /// Like `pareto_front`, where the input of each morphism after the first
+    /// is determined by the propagation policy.
+    pub fn pareto_front_propagating<Id, M, Obj, Size, Cost, P, const NON_NEGATIVE: bool>(
+        &self,
+        category: &Category<Id, M, Obj>,
+        source: Id,
+        target: Id,
+        input_size: Size,
+        propagation: &P,
+    ) -> Vec<WellFormedPath<Id, M, Obj, Size, Cost>>
+    where
+        Id: Key,
+        Obj: Object<Id>,
+        M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+        Size: PathfindingSize,
+        Cost: VectorCost,
+        P: SizePropagation<Obj, Size>,
+    {
+        self.pareto_front_filtered(category, source, target, input_size, propagation, &|_| true)
+    }
+
+    /// Like `pareto_front_propagating`, but a morphism is never used if
+    /// `allows` returns false for it.
+    pub fn pareto_front_filtered<Id, M, Obj, Size, Cost, P, const NON_NEGATIVE: bool>(
+        &self,
+        category: &Category<Id, M, Obj>,
+        source: Id,
+        target: Id,
+        input_size: Size,
+        propagation: &P,
+        allows: &impl Fn(&Morphism<Id, M>) -> bool,
+    ) -> Vec<WellFormedPath<Id, M, Obj, Size, Cost>>
+    where
+        Id: Key,
+        Obj: Object<Id>,
+        M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+        Size: PathfindingSize,
+        Cost: VectorCost,
+        P: SizePropagation<Obj, Size>,
+    {
+        pareto::pareto_paths(category, source, target, input_size, propagation, allows)
+    }
+}
+
+/// Splits an input between several paths from the same source to the same
+/// target. This is useful when the cost of a path grows faster than its input,
+/// so that a large input is cheaper when it is divided.
+///
+/// The input is divided into `chunks` equal parts, which are allocated one at a
+/// time to whichever of up to `paths` candidate paths improves the objective
+/// the most. The candidates never share a morphism, so each morphism is only
+/// applied once, to all of the input that it receives. The result is optimal,
+/// up to the precision of the chunk size, if each path's cost is convex or its
+/// output is concave in its input.
+///
+/// Accumulates Size information in the same way as Accumulating. The candidates
+/// are selected with dijkstra, so costs cannot be negative.
+pub struct SplitRouting {
+    pub paths: usize,
+    pub chunks: u32,
+    pub objective: SplitObjective,
+}
+
+impl SplitRouting {
+    /// Returns each path with the amount of the input that it receives. Paths
+    /// that receive no input are omitted.
+    ///
+    /// Returns None if there is no path, or if some part of the input cannot
+    /// be allocated to any path because a morphism fails to apply to it.
+    pub fn split_route<Id, M, Obj, Size, Cost>(
+        &self,
+        category: &Category<Id, M, Obj>,
+        source: Id,
+        target: Id,
+        input_size: Size,
+    ) -> Option<SplitRoute<Id, M, Obj, Size, Cost>>
+    where
+        Id: Key,
+        Obj: Object<Id>,
+        M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, true>,
+        Size: PathfindingSize + Ord + Add<Output = Size> + Div<Output = Size> + From<u32>,
+        Cost: CostMeasure,
+    {
+        self.split_route_propagating(category, source, target, input_size, &Accumulate)
+    }
+
+    /// Like `split_route`, where the input of each morphism after the first is
+    /// determined by the propagation policy.
+    pub fn split_route_propagating<Id, M, Obj, Size, Cost, P>(
+        &self,
+        category: &Category<Id, M, Obj>,
+        source: Id,
+        target: Id,
+        input_size: Size,
+        propagation: &P,
+    ) -> Option<SplitRoute<Id, M, Obj, Size, Cost>>
+    where
+        Id: Key,
+        Obj: Object<Id>,
+        M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, true>,
+        Size: PathfindingSize + Ord + Add<Output = Size> + Div<Output = Size> + From<u32>,
+        Cost: CostMeasure,
+        P: SizePropagation<Obj, Size>,
+    {
+        self.split_route_filtered(category, source, target, input_size, propagation, &|_| true)
+    }
+
+    /// Like `split_route_propagating`, but a morphism is never used if
+    /// `allows` returns false for it.
+    pub fn split_route_filtered<Id, M, Obj, Size, Cost, P>(
+        &self,
+        category: &Category<Id, M, Obj>,
+        source: Id,
+        target: Id,
+        input_size: Size,
+        propagation: &P,
+        allows: &impl Fn(&Morphism<Id, M>) -> bool,
+    ) -> Option<SplitRoute<Id, M, Obj, Size, Cost>>
+    where
+        Id: Key,
+        Obj: Object<Id>,
+        M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, true>,
+        Size: PathfindingSize + Ord + Add<Output = Size> + Div<Output = Size> + From<u32>,
+        Cost: CostMeasure,
+        P: SizePropagation<Obj, Size>,
+    {
+        split::split_route(
+            category,
+            source,
+            target,
+            input_size,
+            self.paths,
+            self.chunks,
+            self.objective,
+            propagation,
+            allows,
+        )
     }
 }
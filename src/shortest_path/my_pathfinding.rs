@@ -1,10 +1,14 @@
-use std::collections::HashSet;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
 use std::hash::Hash;
+use std::ops::Add;
 
 use crate::category::Key;
 use crate::category::Object;
-use crate::morphism::ApplyMorphism;
-use crate::morphism::MorphismMeta;
+use crate::cost::CostMeasure;
+use crate::morphism::TryApplyMorphism;
+use crate::morphism::{KeyOf, Morphism, MorphismMeta};
+use crate::propagation::SizePropagation;
 use crate::vertex::LeanVertex;
 use crate::vertex::Vertex;
 use pathfinding::num_traits::Zero;
@@ -12,23 +16,37 @@ use pathfinding::prelude::yen;
 
 use crate::category::Category;
 
-use super::path::{Path, WellFormedPath};
+use super::{
+    path::{reapply, Path, WellFormedPath},
+    tree::ShortestPathTree,
+    Heuristic,
+};
 
 // TODO: clarify the names of these functions, and probably the optimizer methods too
 
 pub fn shortest_single_path_with_dijkstra<
     Id: Key,
     Obj: Object<Id>,
-    M: MorphismMeta + ApplyMorphism<Size, Cost, true>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, true>,
     Size: PathfindingSize,
-    Cost: PathfindingCost,
+    Cost: CostMeasure,
+    P: SizePropagation<Obj, Size>,
 >(
     category: &Category<Id, M, Obj>,
     source: Id,
     target: Id,
     input_size: Size,
+    propagation: &P,
+    allows: &impl Fn(&Morphism<Id, M>) -> bool,
 ) -> Option<WellFormedPath<Id, M, Obj, Size, Cost>> {
-    inaccurate_shortest_single_path_with_dijkstra_yen(category, source, target, input_size, 1).pop()
+    inaccurate_shortest_single_path_with_dijkstra(
+        category,
+        source,
+        target,
+        input_size,
+        propagation,
+        allows,
+    )
 }
 
 /// This is considered "inaccurate" because it does not express the requirement
@@ -40,36 +58,270 @@ pub(crate) fn inaccurate_shortest_single_path_with_dijkstra<
     const NON_NEGATIVE: bool,
     Id: Key,
     Obj: Object<Id>,
-    M: MorphismMeta + ApplyMorphism<Size, Cost, NON_NEGATIVE>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
     Size: PathfindingSize,
-    Cost: PathfindingCost,
+    Cost: CostMeasure,
+    P: SizePropagation<Obj, Size>,
 >(
     category: &Category<Id, M, Obj>,
     source: Id,
     target: Id,
     input_size: Size,
+    propagation: &P,
+    allows: &impl Fn(&Morphism<Id, M>) -> bool,
 ) -> Option<WellFormedPath<Id, M, Obj, Size, Cost>> {
-    inaccurate_shortest_single_path_with_dijkstra_yen(category, source, target, input_size, 1).pop()
+    if source == target
+        || category.get_object(&source).is_none()
+        || category.get_object(&target).is_none()
+    {
+        return None;
+    }
+    let searched = search_states(
+        category,
+        (source, input_size.clone()),
+        Some(&target),
+        &input_size,
+        propagation,
+        allows,
+    );
+    let &(node, cost) = searched.objects.get(&target)?;
+    let items = lean_path(&searched.nodes, node);
+    Some(to_path(category, items, input_size, cost, propagation))
 }
 
-/// This is considered "inaccurate" because it does not express the requirement
-/// through the type system that costs must be non-negative, which is a
-/// requirement for dijkstra to provide accurate results. This function may
-/// return a sub-optimal path if you cannot guarantee the cost to be
-/// non-negative.
-pub(crate) fn inaccurate_shortest_single_path_with_dijkstra_yen<
+/// The result of `search_states`
+struct Searched<Id, M, Size, Cost> {
+    nodes: Vec<SettledNode<Id, M, Size, Cost>>,
+    /// The node where each object was first expanded, and its total cost
+    objects: HashMap<Id, (usize, Cost)>,
+}
+
+/// Runs dijkstra over (object, size) states from the start, until the target
+/// is expanded or, without a target, until every reachable state is expanded.
+///
+/// An object is never entered again once one of its states is expanded, which
+/// guarantees termination. A state that reached the object before that is
+/// still expanded, because a more expensive size may lead to a cheaper path
+/// further on.
+fn search_states<const NON_NEGATIVE: bool, Id, Obj, M, Size, Cost, P>(
+    category: &Category<Id, M, Obj>,
+    start: (Id, Size),
+    target: Option<&Id>,
+    original: &Size,
+    propagation: &P,
+    allows: &impl Fn(&Morphism<Id, M>) -> bool,
+) -> Searched<Id, M, Size, Cost>
+where
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+    Size: PathfindingSize,
+    Cost: CostMeasure,
+    P: SizePropagation<Obj, Size>,
+{
+    let mut best = HashMap::from([(start.clone(), Cost::zero())]);
+    let mut nodes = vec![(start, None)];
+    let mut expanded = HashSet::new();
+    let mut objects = HashMap::new();
+    let mut queue = BinaryHeap::from([Reverse((Cost::zero(), 0))]);
+
+    while let Some(Reverse((cost, node))) = queue.pop() {
+        let (id, size) = nodes[node].0.clone();
+        if !expanded.insert((id.clone(), size.clone())) {
+            continue;
+        }
+        objects.entry(id.clone()).or_insert((node, cost));
+        if target == Some(&id) {
+            break;
+        }
+        for morphism in category.get_outbound(&id).into_iter().flatten() {
+            if objects.contains_key(&morphism.target) || !allows(morphism) {
+                continue;
+            }
+            let Some((next, next_size, step_cost)) =
+                morphism.successor(category, size.clone(), original, propagation)
+            else {
+                continue;
+            };
+            let next_cost = cost.plus(step_cost);
+            let state = (next, next_size);
+            if best.get(&state).is_some_and(|&known| known <= next_cost) {
+                continue;
+            }
+            best.insert(state.clone(), next_cost);
+            nodes.push((state, Some((node, morphism.clone(), step_cost))));
+            queue.push(Reverse((next_cost, nodes.len() - 1)));
+        }
+    }
+    Searched { nodes, objects }
+}
+
+/// The vertices of the path that leads to the node, where each vertex only
+/// refers to its object by id.
+fn lean_path<Id: Clone, M: Clone, Size: Clone, Cost>(
+    nodes: &[SettledNode<Id, M, Size, Cost>],
+    node: usize,
+) -> Vec<LeanVertex<Id, M, Size>> {
+    let mut items = vec![];
+    let mut current = node;
+    loop {
+        let ((id, size), predecessor) = &nodes[current];
+        items.push(LeanVertex::Object {
+            inner: id.clone(),
+            size: size.clone(),
+        });
+        let Some((previous, morphism, _)) = predecessor else {
+            break;
+        };
+        items.push(LeanVertex::Morphism {
+            inner: morphism.clone(),
+            input: nodes[*previous].0 .1.clone(),
+            cost: (),
+        });
+        current = *previous;
+    }
+    items.reverse();
+    items
+}
+
+/// Runs dijkstra once from the source to every object, and returns the tree
+/// of the cheapest path to each of them. Each path is the same one that
+/// `shortest_single_path_with_dijkstra` returns for its target.
+///
+/// Returns None if the source is not in the category.
+pub fn shortest_path_tree_with_dijkstra<
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, true>,
+    Size: PathfindingSize,
+    Cost: CostMeasure,
+    P: SizePropagation<Obj, Size>,
+>(
+    category: &Category<Id, M, Obj>,
+    source: Id,
+    input_size: Size,
+    propagation: &P,
+    allows: &impl Fn(&Morphism<Id, M>) -> bool,
+) -> Option<ShortestPathTree<Id, M, Obj, Size, Cost>> {
+    category.get_object(&source)?;
+    let searched = search_states(
+        category,
+        (source, input_size.clone()),
+        None,
+        &input_size,
+        propagation,
+        allows,
+    );
+    let objects = searched
+        .objects
+        .into_iter()
+        .map(|(id, (node, _))| (id, node))
+        .collect();
+    Some(ShortestPathTree::from_states(
+        category,
+        searched.nodes,
+        objects,
+    ))
+}
+
+/// A state reached by `settle_objects`, and the previous node with the
+/// morphism and its cost that lead from it.
+pub(super) type SettledNode<Id, M, Size, Cost> =
+    ((Id, Size), Option<(usize, Morphism<Id, M>, Cost)>);
+
+/// The result of `settle_objects`
+pub(super) struct Settled<Id, M, Size, Cost> {
+    pub nodes: Vec<SettledNode<Id, M, Size, Cost>>,
+    /// The node where each object was settled
+    pub objects: HashMap<Id, usize>,
+}
+
+/// Runs dijkstra over (object, size) states from the start, until the target
+/// is settled or, without a target, until every reachable object is settled.
+/// Every object is settled once, at the first size that reaches it, so the
+/// path to any node never revisits an object.
+///
+/// The excluded objects are never reached, and the start is never left with
+/// an excluded morphism. Morphisms that are not allowed are never used.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn settle_objects<const NON_NEGATIVE: bool, Id, Obj, M, Size, Cost, P>(
+    category: &Category<Id, M, Obj>,
+    start: (Id, Size),
+    target: Option<&Id>,
+    excluded_objects: &HashSet<Id>,
+    excluded_morphisms: &HashSet<KeyOf<Id, M>>,
+    original: &Size,
+    propagation: &P,
+    allows: &impl Fn(&Morphism<Id, M>) -> bool,
+) -> Settled<Id, M, Size, Cost>
+where
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+    Size: Clone,
+    Cost: CostMeasure,
+    P: SizePropagation<Obj, Size>,
+{
+    let mut nodes = vec![(start, None)];
+    let mut objects = HashMap::new();
+    let mut queue = BinaryHeap::from([Reverse((Cost::zero(), 0))]);
+
+    while let Some(Reverse((cost, node))) = queue.pop() {
+        let (id, size) = nodes[node].0.clone();
+        if objects.contains_key(&id) {
+            continue;
+        }
+        objects.insert(id.clone(), node);
+        if target == Some(&id) {
+            break;
+        }
+        for morphism in category.get_outbound(&id).into_iter().flatten() {
+            if excluded_objects.contains(&morphism.target)
+                || objects.contains_key(&morphism.target)
+                || (node == 0 && excluded_morphisms.contains(&morphism.key()))
+                || !allows(morphism)
+            {
+                continue;
+            }
+            let Some((next, next_size, step_cost)) =
+                morphism.successor(category, size.clone(), original, propagation)
+            else {
+                continue;
+            };
+            nodes.push(((next, next_size), Some((node, morphism.clone(), step_cost))));
+            queue.push(Reverse((cost.plus(step_cost), nodes.len() - 1)));
+        }
+    }
+    Settled { nodes, objects }
+}
+
+/// Like `inaccurate_shortest_single_path_with_dijkstra`, but returns up to
+/// `n_paths` paths with yen, and no path has more than `max_morphisms`
+/// morphisms. The hop count is part of each search state, so the cheapest path
+/// within the limit is found even when a cheaper path exists with more
+/// morphisms.
+///
+/// Objects are not blacklisted, because the limit already guarantees that the
+/// search terminates. An object may appear more than once in a path, but only
+/// if that makes the path cheaper.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn inaccurate_hop_limited_dijkstra_yen<
     const NON_NEGATIVE: bool,
     Id: Key,
     Obj: Object<Id>,
-    M: MorphismMeta + ApplyMorphism<Size, Cost, NON_NEGATIVE>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
     Size: PathfindingSize,
-    Cost: PathfindingCost,
+    Cost: CostMeasure,
+    P: SizePropagation<Obj, Size>,
 >(
     category: &Category<Id, M, Obj>,
     source: Id,
     target: Id,
     input_size: Size,
+    max_morphisms: usize,
     n_paths: usize,
+    propagation: &P,
+    allows: &impl Fn(&Morphism<Id, M>) -> bool,
 ) -> Vec<WellFormedPath<Id, M, Obj, Size, Cost>> {
     if source == target
         || category.get_object(&source).is_none()
@@ -77,42 +329,203 @@ pub(crate) fn inaccurate_shortest_single_path_with_dijkstra_yen<
     {
         return vec![];
     }
-    let start_vertex = LeanVertex::Object {
-        inner: source,
-        size: input_size,
-    };
-    let mut already_seen = HashSet::new();
+    let start = (
+        LeanVertex::Object {
+            inner: source,
+            size: input_size.clone(),
+        },
+        0,
+    );
     let mut ret = yen(
-        &start_vertex,
-        move |n| n.blacklisted_successors(category, &mut already_seen),
-        move |n| n.is_object_with_id(&target),
+        &start,
+        |(n, hops)| match n {
+            LeanVertex::Object { .. } if *hops >= max_morphisms => vec![],
+            LeanVertex::Object { .. } => n
+                .successors(category, &input_size, propagation, allows)
+                .into_iter()
+                .map(|(next, cost)| ((next, *hops), Measure(cost)))
+                .collect(),
+            LeanVertex::Morphism { .. } => n
+                .successors(category, &input_size, propagation, allows)
+                .into_iter()
+                .map(|(next, cost)| ((next, hops + 1), Measure(cost)))
+                .collect(),
+        },
+        move |(n, _)| n.is_object_with_id(&target),
         n_paths,
     )
     .into_iter()
     .map(|(items, cost)| {
-        WellFormedPath(Path {
-            vertices: items
-                .into_iter()
-                .map(|v| Vertex::from(v, category))
-                .collect::<Vec<_>>()
-                .try_into()
-                .expect("would be none, not empty"),
-            cost,
-        })
+        let items = items.into_iter().map(|(n, _)| n).collect();
+        to_path(category, items, input_size.clone(), cost.0, propagation)
     })
     .collect::<Vec<WellFormedPath<Id, M, Obj, Size, Cost>>>();
 
-    // while the returned value is expected to be sorted, dijkstra being
-    // well-behaved is based on the assumption that costs are positive. this
-    // sort is an insurance mechanism to ensure that the result is sorted
-    // properly even if the assumptions are not satsified.
+    // dijkstra being well-behaved is based on the assumption that costs are
+    // positive. this sort ensures that the result is sorted properly even if
+    // the assumption is not satisfied.
     ret.sort_by_key(|wfp| wfp.cost);
 
     ret
 }
 
-pub trait PathfindingCost: Zero + Eq + Hash + Clone + Ord + Copy {}
-impl<T: Zero + Eq + Hash + Clone + Ord + Copy> PathfindingCost for T {}
+/// Uses A* to find the cheapest path while accumulating sizes. The heuristic
+/// must never overestimate the remaining cost to reach the target, otherwise
+/// the returned path may be sub-optimal.
+///
+/// Unlike the dijkstra implementation, objects are not blacklisted for the
+/// whole search, because states are not expanded in order of their cost: a
+/// more expensive state with a lower estimate may be expanded first, and must
+/// not block a cheaper way through the same object. Instead, an (object, size)
+/// state is only expanded again if it is reached more cheaply, and a path
+/// never revisits one of its own objects, which guarantees that the search
+/// terminates.
+pub fn shortest_single_path_with_astar<
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, true>,
+    Size: PathfindingSize,
+    Cost: CostMeasure,
+    H: Heuristic<Id, Size, Cost>,
+    P: SizePropagation<Obj, Size>,
+>(
+    category: &Category<Id, M, Obj>,
+    source: Id,
+    target: Id,
+    input_size: Size,
+    heuristic: &H,
+    propagation: &P,
+    allows: &impl Fn(&Morphism<Id, M>) -> bool,
+) -> Option<WellFormedPath<Id, M, Obj, Size, Cost>> {
+    if source == target
+        || category.get_object(&source).is_none()
+        || category.get_object(&target).is_none()
+    {
+        return None;
+    }
+    let estimate = heuristic.estimate(&source, &input_size);
+    let mut nodes: Vec<SettledNode<Id, M, Size, Cost>> = vec![((source, input_size.clone()), None)];
+    let mut costs = vec![Cost::zero()];
+    // the cost of each (object, size) state when it was last expanded
+    let mut expanded = HashMap::new();
+    let mut queue = BinaryHeap::from([Reverse((estimate, 0))]);
+
+    let goal = loop {
+        let Reverse((_, node)) = queue.pop()?;
+        let (id, size) = nodes[node].0.clone();
+        let state = (id.clone(), size.clone());
+        if expanded
+            .get(&state)
+            .is_some_and(|&cost| cost <= costs[node])
+        {
+            continue;
+        }
+        expanded.insert(state, costs[node]);
+        if id == target {
+            break node;
+        }
+        for morphism in category.get_outbound(&id).into_iter().flatten() {
+            if !allows(morphism) || is_on_path(&nodes, node, &morphism.target) {
+                continue;
+            }
+            let Some((next, next_size, step_cost)) =
+                morphism.successor(category, size.clone(), &input_size, propagation)
+            else {
+                continue;
+            };
+            let cost = costs[node].plus(step_cost);
+            if expanded
+                .get(&(next.clone(), next_size.clone()))
+                .is_some_and(|&known| known <= cost)
+            {
+                continue;
+            }
+            let estimate = cost.plus(heuristic.estimate(&next, &next_size));
+            nodes.push(((next, next_size), Some((node, morphism.clone(), step_cost))));
+            costs.push(cost);
+            queue.push(Reverse((estimate, nodes.len() - 1)));
+        }
+    };
+
+    Some(to_path(
+        category,
+        lean_path(&nodes, goal),
+        input_size,
+        costs[goal],
+        propagation,
+    ))
+}
+
+/// Converts the vertices found by a search into a path, filling in the cost of
+/// each morphism by reapplying it to the same input that the search used.
+/// Morphisms that fail were pruned from the search, so reapplying cannot fail.
+fn to_path<const NON_NEGATIVE: bool, Id, Obj, M, Size, Cost, P>(
+    category: &Category<Id, M, Obj>,
+    items: Vec<LeanVertex<Id, M, Size>>,
+    input_size: Size,
+    cost: Cost,
+    propagation: &P,
+) -> WellFormedPath<Id, M, Obj, Size, Cost>
+where
+    P: SizePropagation<Obj, Size>,
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+    Size: Clone,
+    Cost: CostMeasure,
+{
+    let Ok((vertices, _)) = reapply(
+        items.into_iter().map(|v| Vertex::from(v, category)),
+        input_size,
+        propagation,
+    ) else {
+        unreachable!("the search already applied each morphism to the same input")
+    };
+    WellFormedPath(Path {
+        vertices: vertices.try_into().expect("would be none, not empty"),
+        cost,
+    })
+}
+
+/// True if the object is on the path that leads to the node, including the
+/// node itself.
+fn is_on_path<Id: PartialEq, M, Size, Cost>(
+    nodes: &[SettledNode<Id, M, Size, Cost>],
+    node: usize,
+    object: &Id,
+) -> bool {
+    let mut current = Some(node);
+    while let Some(node) = current {
+        let ((id, _), predecessor) = &nodes[node];
+        if id == object {
+            return true;
+        }
+        current = predecessor.as_ref().map(|(previous, _, _)| *previous);
+    }
+    false
+}
+
+/// Adapts a CostMeasure to the traits required by pathfinding
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Measure<Cost>(Cost);
+
+impl<Cost: CostMeasure> Add for Measure<Cost> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Measure(self.0.plus(rhs.0))
+    }
+}
+
+impl<Cost: CostMeasure> Zero for Measure<Cost> {
+    fn zero() -> Self {
+        Measure(Cost::zero())
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0 == Cost::zero()
+    }
+}
 
 pub trait PathfindingSize: Clone + Eq + Hash {}
 impl<T: Clone + Eq + Hash> PathfindingSize for T {}
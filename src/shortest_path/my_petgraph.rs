@@ -1,17 +1,23 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, convert::Infallible, ops::Add};
 
 use petgraph::{
-    algo::{bellman_ford, FloatMeasure},
+    algo::{bellman_ford, find_negative_cycle, FloatMeasure},
     stable_graph::NodeIndex,
     Graph,
 };
 use thiserror::Error;
 
-use super::path::{reapply, sum_petgraph, Path, WellFormedPath};
+use super::{
+    path::{reapply, ApplicationError, InvalidPath, Path, WellFormedPath},
+    tree::ShortestPathTree,
+};
 use crate::{
     category::{Category, Key, Object},
-    morphism::ApplyMorphism,
-    morphism::MorphismMeta,
+    collections::SomeVec,
+    cost::CostMeasure,
+    morphism::TryApplyMorphism,
+    morphism::{Morphism, MorphismMeta},
+    propagation::{Accumulate, SizePropagation},
     vertex::{LeanVertex, Vertex},
 };
 
@@ -24,29 +30,32 @@ use crate::{
 /// is found, it will be re-applied with accumulation. This means that there may
 /// be some error in terms of which path has been selected, but there will be no
 /// error in the values contained within the returned path.
+///
+/// Morphisms that fail to apply to `input_size`, or that are not allowed, are
+/// excluded from the search. If a morphism in the selected path fails once
+/// sizes are propagated, an error is returned that identifies it.
 #[allow(clippy::type_complexity)]
 pub fn shortest_single_path_with_bellman_ford<
     const NON_NEGATIVE: bool,
     Id: Key,
     Obj: Object<Id>,
-    M: MorphismMeta + ApplyMorphism<Size, Cost, NON_NEGATIVE>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
     Size: Clone,
-    Cost: FloatMeasure,
+    Cost: CostMeasure,
+    P: SizePropagation<Obj, Size>,
 >(
     category: &Category<Id, M, Obj>,
     source: Id,
     target: Id,
     input_size: Size,
-) -> Result<Option<WellFormedPath<Id, M, Obj, Size, Cost>>, PathFindingError<Id>> {
-    let mut general =
-        shortest_multi_path_with_bellman_ford(category, source, &[target], input_size)?;
-    if general.is_empty() {
-        Ok(None)
-    } else {
-        // function below should guarantee return of at most a single item when
-        // `targets` has a length of 1
-        Ok(Some(general.swap_remove(0)))
-    }
+    propagation: &P,
+    allows: &impl Fn(&Morphism<Id, M>) -> bool,
+) -> Result<Option<WellFormedPath<Id, M, Obj, Size, Cost>>, PathFindingError<Id, M, M::Error>> {
+    CompiledCategory::filtered(category, input_size, allows).shortest_path_propagating(
+        source,
+        target,
+        propagation,
+    )
 }
 
 /// Finds the most cost-efficient paths from the source to each target using the
@@ -59,77 +68,462 @@ pub fn shortest_single_path_with_bellman_ford<
 /// are found, they will be re-applied with accumulation. This means that there
 /// may be some error in terms of which paths have been selected, but there will
 /// be no error in the values contained within the returned paths.
+///
+/// Morphisms that fail to apply to `input_size` are excluded from the search.
+/// If a morphism in a selected path fails once sizes are propagated, an error
+/// is returned that identifies it.
 #[allow(clippy::type_complexity)]
 pub fn shortest_multi_path_with_bellman_ford<
     const NON_NEGATIVE: bool,
     Id: Key,
     Obj: Object<Id>,
-    M: MorphismMeta + ApplyMorphism<Size, Cost, NON_NEGATIVE>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
     Size: Clone,
-    Cost: FloatMeasure,
+    Cost: CostMeasure,
+    P: SizePropagation<Obj, Size>,
 >(
     category: &Category<Id, M, Obj>,
     source: Id,
     targets: &[Id],
     input_size: Size,
-) -> Result<Vec<WellFormedPath<Id, M, Obj, Size, Cost>>, PathFindingError<Id>> {
-    if targets.is_empty() || category.get_object(&source).is_none() {
-        return Ok(vec![]);
+    propagation: &P,
+) -> Result<Vec<WellFormedPath<Id, M, Obj, Size, Cost>>, PathFindingError<Id, M, M::Error>> {
+    CompiledCategory::new(category, input_size).shortest_paths_propagating(
+        source,
+        targets,
+        propagation,
+    )
+}
+
+/// Runs bellman-ford once from the source to every object, and returns the
+/// tree of the cheapest path to each of them.
+///
+/// Paths are selected in the same way as `shortest_multi_path_with_bellman_ford`,
+/// with the same `input_size` for every morphism. Sizes and costs are then
+/// accumulated along the tree. An error is returned if a morphism in the tree
+/// fails once sizes are propagated. Returns None if the source is not in the
+/// category.
+#[allow(clippy::type_complexity)]
+pub fn shortest_path_tree_with_bellman_ford<
+    const NON_NEGATIVE: bool,
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+    Size: Clone,
+    Cost: CostMeasure,
+    P: SizePropagation<Obj, Size>,
+>(
+    category: &Category<Id, M, Obj>,
+    source: Id,
+    input_size: Size,
+    propagation: &P,
+    allows: &impl Fn(&Morphism<Id, M>) -> bool,
+) -> Result<Option<ShortestPathTree<Id, M, Obj, Size, Cost>>, PathFindingError<Id, M, M::Error>> {
+    CompiledCategory::filtered(category, input_size, allows)
+        .shortest_path_tree_propagating(source, propagation)
+}
+
+/// Finds the most cost-efficient path from source to target that has at most
+/// `max_morphisms` morphisms, using bellman-ford limited to that many rounds.
+/// Each round only extends the paths found by the previous round, so the
+/// limit is exact.
+///
+/// Sizes are handled in the same way as `shortest_single_path_with_bellman_ford`.
+/// Negative cycles are not an error, because the limit means there is always
+/// an optimal path. The returned path may include a cycle if that makes it
+/// cheaper. Missing objects are handled in the same way as
+/// `shortest_single_path_with_bellman_ford`: there is no path from a missing
+/// source, and a missing target is an error.
+#[allow(clippy::type_complexity)]
+pub fn shortest_single_path_with_hop_limited_bellman_ford<
+    const NON_NEGATIVE: bool,
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+    Size: Clone,
+    Cost: CostMeasure,
+    P: SizePropagation<Obj, Size>,
+>(
+    category: &Category<Id, M, Obj>,
+    source: Id,
+    target: Id,
+    input_size: Size,
+    max_morphisms: usize,
+    propagation: &P,
+    allows: &impl Fn(&Morphism<Id, M>) -> bool,
+) -> Result<Option<WellFormedPath<Id, M, Obj, Size, Cost>>, PathFindingError<Id, M, M::Error>> {
+    let cg = CategoryGraph::new(category, input_size.clone(), allows);
+    let Some(&source_index) = cg.object_id_to_index.get(&source) else {
+        return Ok(None);
+    };
+    if source == target {
+        return Ok(None);
     }
-    let cg = CategoryGraph::new(category, input_size.clone());
-    let source_index = *cg
+    let target_index = *cg
         .object_id_to_index
-        .get(&source)
-        .ok_or(MissingObject(source.clone()))?;
-    let paths = bellman_ford(&cg.graph, source_index).map_err(|_| NegativeCycle)?;
+        .get(&target)
+        .ok_or(MissingObject(target.clone()))?;
+    let mut distances = vec![None; cg.graph.node_count()];
+    distances[source_index.index()] = Some(Cost::zero());
+    // each morphism is two edges: from its source, and to its target
+    let mut rounds = vec![];
+    for _ in 0..2 * max_morphisms {
+        let previous = distances.clone();
+        let mut predecessors = vec![None; distances.len()];
+        for edge in cg.graph.raw_edges() {
+            let (i, j) = (edge.source().index(), edge.target().index());
+            let Some(distance) = previous[i] else {
+                continue;
+            };
+            let distance = distance.plus(edge.weight.0);
+            if distances[j].is_none_or(|known| distance < known) {
+                distances[j] = Some(distance);
+                predecessors[j] = Some(edge.source());
+            }
+        }
+        rounds.push(predecessors);
+    }
+    if distances[target_index.index()].is_none() {
+        return Ok(None);
+    }
 
-    let mut resolved_paths = vec![];
-    'outer: for target in targets {
-        let target_index = *cg
-            .object_id_to_index
-            .get(target)
-            .ok_or(MissingObject(target.clone()))?;
-        let mut work_back = target_index;
-        let mut path = vec![];
-        while work_back != source_index {
-            path.push(work_back);
-            if paths.predecessors[work_back.index()].is_none() {
-                continue 'outer;
+    // the predecessor from the latest round that improved each node is the one
+    // that its distance is based on
+    let mut path = vec![target_index];
+    for predecessors in rounds.iter().rev() {
+        if let Some(previous) = predecessors[path.last().unwrap().index()] {
+            path.push(previous);
+        }
+    }
+    path.reverse();
+    let unaccumulated_vertices = path
+        .into_iter()
+        .map(|idx| Vertex::from(cg.index_to_vertex[&idx].clone(), category))
+        .collect::<Vec<_>>();
+    let (vertices, cost) = reapply(unaccumulated_vertices, input_size, propagation)?;
+
+    Ok(Some(WellFormedPath(Path {
+        vertices: vertices.try_into().expect("the target was reached"),
+        cost,
+    })))
+}
+
+/// Finds cycles of morphisms whose costs add up to a negative number when
+/// every morphism is applied to `input_size`. Each cycle is returned in the
+/// order that its morphisms would be applied. Every object in the category
+/// is searched, regardless of which objects can reach it.
+///
+/// This does not find every negative cycle in the category. Each returned
+/// cycle is distinct and shares no morphisms with the others, and at least
+/// one cycle is returned if any negative cycle exists.
+pub fn find_negative_cycles<
+    const NON_NEGATIVE: bool,
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+    Size: Clone,
+    Cost: CostMeasure,
+>(
+    category: &Category<Id, M, Obj>,
+    input_size: Size,
+) -> Vec<SomeVec<Morphism<Id, M>>> {
+    let cg = CategoryGraph::new(category, input_size, &|_| true);
+    let graph = &cg.graph;
+    let node_count = graph.node_count();
+    // starting every node at zero is equivalent to searching from a virtual
+    // source with a free edge to every node.
+    let mut distances = vec![Cost::zero(); node_count];
+    let mut predecessors = vec![None; node_count];
+    let relax = |distances: &mut Vec<Cost>, predecessors: &mut Vec<Option<NodeIndex>>| {
+        let mut relaxed = vec![];
+        for edge in graph.raw_edges() {
+            let (i, j) = (edge.source().index(), edge.target().index());
+            if distances[i].plus(edge.weight.0) < distances[j] {
+                distances[j] = distances[i].plus(edge.weight.0);
+                predecessors[j] = Some(edge.source());
+                relaxed.push(edge.target());
             }
-            work_back = paths.predecessors[work_back.index()].unwrap();
         }
-        path.push(work_back);
-        path.reverse();
-        let unaccumulated_vertices = path
-            .into_iter()
-            .map(|idx| cg.index_to_vertex.get(&idx).cloned())
-            .collect::<Option<Vec<_>>>()
-            .unwrap() // todo
-            .into_iter()
-            .map(|v| Vertex::from(v, category))
-            .collect::<Vec<_>>();
-        // let cost = paths.distances[target_index.index()]; // incorrect: based on unaccumulated morphism outputs
-        let (vertices, costs) = reapply(unaccumulated_vertices, input_size.clone());
-        resolved_paths.push(WellFormedPath(Path {
-            vertices: vertices.try_into().expect("`continue 'outer` avoids this"),
-            cost: sum_petgraph(costs),
-        }));
+        relaxed
+    };
+    for _ in 1..node_count {
+        if relax(&mut distances, &mut predecessors).is_empty() {
+            return vec![];
+        }
+    }
+
+    let mut cycles = vec![];
+    let mut in_cycle = vec![false; node_count];
+    for node in relax(&mut distances, &mut predecessors) {
+        // walking back through every node is guaranteed to land in a cycle
+        let start = (0..node_count).try_fold(node, |current, _| predecessors[current.index()]);
+        let Some(start) = start.filter(|start| !in_cycle[start.index()]) else {
+            continue;
+        };
+        let mut cycle = vec![start];
+        let mut current = predecessors[start.index()].unwrap();
+        while current != start {
+            cycle.push(current);
+            current = predecessors[current.index()].unwrap();
+        }
+        cycle.reverse();
+        for index in &cycle {
+            in_cycle[index.index()] = true;
+        }
+        cycles.push(cg.morphisms_in(cycle));
     }
 
-    Ok(resolved_paths)
+    cycles
 }
 
+/// `E` is the error of a morphism that fails to apply. It is only used by
+/// optimizers that reapply a path after selecting it.
 #[derive(Error, Debug)]
-pub enum PathFindingError<Id: std::fmt::Debug> {
+pub enum PathFindingError<Id: std::fmt::Debug, M, E = Infallible> {
     #[error("The object could not be identified as a vertex in the underlying graph")]
     MissingObject(Id),
+    /// Contains the morphisms that form the cycle, in the order they would be
+    /// applied. For some use cases, such as arbitrage, this is an opportunity
+    /// rather than a problem.
     #[error("There is a cycle of negative costs that prevent shortest path optimization")]
-    NegativeCycle,
+    NegativeCycle(SomeVec<Morphism<Id, M>>),
+    #[error("The search discovered more than the maximum of {0} states")]
+    StateBudgetExceeded(usize),
+    /// Contains the morphisms that form the cycle, in the order they would be
+    /// applied. Like a negative cycle, this may be an opportunity.
+    #[error("There is a cycle of morphisms that increases the output size without bound")]
+    UnboundedOutput(SomeVec<Morphism<Id, M>>),
+    /// A morphism in the selected path could not be applied to the size that
+    /// was propagated to it.
+    #[error("{0}")]
+    ApplicationFailed(ApplicationError<Id, M, E>),
+    /// A path could not be reapplied because it is not well formed.
+    #[error("{0}")]
+    InvalidPath(#[from] InvalidPath),
 }
 use PathFindingError::*;
 
+/// Adapts a CostMeasure to the traits required by petgraph. Adding anything to
+/// an infinite cost remains infinite, so unreachable vertices are never
+/// mistaken for reachable ones, and integer costs do not overflow.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+struct Measure<Cost>(Cost);
+
+impl<Cost: CostMeasure> Add for Measure<Cost> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        if self.0 == Cost::infinite() || rhs.0 == Cost::infinite() {
+            Measure(Cost::infinite())
+        } else {
+            Measure(self.0.plus(rhs.0))
+        }
+    }
+}
+
+impl<Cost: CostMeasure> Default for Measure<Cost> {
+    fn default() -> Self {
+        Measure(Cost::zero())
+    }
+}
+
+impl<Cost: CostMeasure> FloatMeasure for Measure<Cost> {
+    fn zero() -> Self {
+        Measure(Cost::zero())
+    }
+
+    fn infinite() -> Self {
+        Measure(Cost::infinite())
+    }
+}
+
+/// The graph that bellman-ford searches for one category and input size,
+/// built once so that it can serve any number of queries.
+///
+/// Building the graph applies every morphism to `input_size`, and stores each
+/// cost as the weight of an edge. Queries only run bellman-ford over the graph
+/// and then reapply the selected paths, in the same way as the `Negatable`
+/// optimizer. The category must not change while it is compiled, which is
+/// enforced by the borrow.
+pub struct CompiledCategory<'a, Id, M, Obj, Size, Cost, const NON_NEGATIVE: bool = false>
+where
+    M: MorphismMeta,
+{
+    category: &'a Category<Id, M, Obj>,
+    input_size: Size,
+    graph: CategoryGraph<Id, M, Size, Cost, NON_NEGATIVE>,
+}
+
+impl<'a, Id, M, Obj, Size, Cost, const NON_NEGATIVE: bool>
+    CompiledCategory<'a, Id, M, Obj, Size, Cost, NON_NEGATIVE>
+where
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+    Size: Clone,
+    Cost: CostMeasure,
+{
+    pub fn new(category: &'a Category<Id, M, Obj>, input_size: Size) -> Self {
+        Self::filtered(category, input_size, &|_| true)
+    }
+
+    /// Like `new`, but the graph only contains the morphisms for which `allows`
+    /// returns true, so no query can use the others.
+    pub fn filtered(
+        category: &'a Category<Id, M, Obj>,
+        input_size: Size,
+        allows: &impl Fn(&Morphism<Id, M>) -> bool,
+    ) -> Self {
+        Self {
+            category,
+            graph: CategoryGraph::new(category, input_size.clone(), allows),
+            input_size,
+        }
+    }
+
+    pub fn input_size(&self) -> &Size {
+        &self.input_size
+    }
+
+    /// Returns the cheapest path from source to target, where the output of
+    /// each morphism is the input of the next.
+    #[allow(clippy::type_complexity)]
+    pub fn shortest_path(
+        &self,
+        source: Id,
+        target: Id,
+    ) -> Result<Option<WellFormedPath<Id, M, Obj, Size, Cost>>, PathFindingError<Id, M, M::Error>>
+    {
+        self.shortest_path_propagating(source, target, &Accumulate)
+    }
+
+    /// Like `shortest_path`, where the input of each morphism after the first
+    /// is determined by the propagation policy.
+    #[allow(clippy::type_complexity)]
+    pub fn shortest_path_propagating<P: SizePropagation<Obj, Size>>(
+        &self,
+        source: Id,
+        target: Id,
+        propagation: &P,
+    ) -> Result<Option<WellFormedPath<Id, M, Obj, Size, Cost>>, PathFindingError<Id, M, M::Error>>
+    {
+        let mut general = self.shortest_paths_propagating(source, &[target], propagation)?;
+        // at most one path is returned for a single target
+        Ok(general.pop())
+    }
+
+    /// Returns the cheapest path from the source to each target that it can
+    /// reach, with a single search.
+    #[allow(clippy::type_complexity)]
+    pub fn shortest_paths(
+        &self,
+        source: Id,
+        targets: &[Id],
+    ) -> Result<Vec<WellFormedPath<Id, M, Obj, Size, Cost>>, PathFindingError<Id, M, M::Error>>
+    {
+        self.shortest_paths_propagating(source, targets, &Accumulate)
+    }
+
+    /// Like `shortest_paths`, where the input of each morphism after the first
+    /// is determined by the propagation policy.
+    #[allow(clippy::type_complexity)]
+    pub fn shortest_paths_propagating<P: SizePropagation<Obj, Size>>(
+        &self,
+        source: Id,
+        targets: &[Id],
+        propagation: &P,
+    ) -> Result<Vec<WellFormedPath<Id, M, Obj, Size, Cost>>, PathFindingError<Id, M, M::Error>>
+    {
+        if targets.is_empty() || self.category.get_object(&source).is_none() {
+            return Ok(vec![]);
+        }
+        let (source_index, predecessors) = self.graph.bellman_ford(&source)?;
+
+        let mut resolved_paths = vec![];
+        'outer: for target in targets {
+            if target == &source {
+                // a path needs at least one morphism
+                continue;
+            }
+            let target_index = *self
+                .graph
+                .object_id_to_index
+                .get(target)
+                .ok_or(MissingObject(target.clone()))?;
+            let mut work_back = target_index;
+            let mut path = vec![];
+            while work_back != source_index {
+                path.push(work_back);
+                let Some(predecessor) = predecessors[work_back.index()] else {
+                    continue 'outer;
+                };
+                work_back = predecessor;
+            }
+            path.push(work_back);
+            path.reverse();
+            let unaccumulated_vertices = path
+                .into_iter()
+                .map(|idx| Vertex::from(self.graph.index_to_vertex[&idx].clone(), self.category))
+                .collect::<Vec<_>>();
+            // the distance in the graph is based on unaccumulated morphism
+            // outputs, so the cost comes from reapplying the path
+            let (vertices, cost) =
+                reapply(unaccumulated_vertices, self.input_size.clone(), propagation)?;
+            resolved_paths.push(WellFormedPath(Path {
+                vertices: vertices.try_into().expect("`continue 'outer` avoids this"),
+                cost,
+            }));
+        }
+
+        Ok(resolved_paths)
+    }
+
+    /// Returns the cheapest path from the source to every object that it can
+    /// reach, with a single search. Returns None if the source is not in the
+    /// category.
+    #[allow(clippy::type_complexity)]
+    pub fn shortest_path_tree(
+        &self,
+        source: Id,
+    ) -> Result<Option<ShortestPathTree<Id, M, Obj, Size, Cost>>, PathFindingError<Id, M, M::Error>>
+    {
+        self.shortest_path_tree_propagating(source, &Accumulate)
+    }
+
+    /// Like `shortest_path_tree`, where the input of each morphism after the
+    /// first is determined by the propagation policy.
+    #[allow(clippy::type_complexity)]
+    pub fn shortest_path_tree_propagating<P: SizePropagation<Obj, Size>>(
+        &self,
+        source: Id,
+        propagation: &P,
+    ) -> Result<Option<ShortestPathTree<Id, M, Obj, Size, Cost>>, PathFindingError<Id, M, M::Error>>
+    {
+        if self.category.get_object(&source).is_none() {
+            return Ok(None);
+        }
+        let (_, predecessors) = self.graph.bellman_ford(&source)?;
+        // the predecessor of an object is always a morphism
+        let predecessors = self.graph.object_id_to_index.values().filter_map(|index| {
+            match &self.graph.index_to_vertex[&predecessors[index.index()]?] {
+                LeanVertex::Morphism { inner, .. } => Some(inner.clone()),
+                LeanVertex::Object { .. } => None,
+            }
+        });
+        ShortestPathTree::from_predecessors(
+            self.category,
+            source,
+            self.input_size.clone(),
+            predecessors,
+            propagation,
+        )
+        .map(Some)
+        .map_err(ApplicationFailed)
+    }
+}
+
 struct CategoryGraph<Id, M, Size, Cost, const NON_NEGATIVE: bool> {
-    graph: Graph<LeanVertex<Id, M, Size>, Cost>,
+    graph: Graph<LeanVertex<Id, M, Size>, Measure<Cost>>,
     object_id_to_index: HashMap<Id, NodeIndex>,
     index_to_vertex: HashMap<NodeIndex, LeanVertex<Id, M, Size>>,
 }
@@ -137,59 +531,56 @@ struct CategoryGraph<Id, M, Size, Cost, const NON_NEGATIVE: bool> {
 impl<Id, M, Size, Cost, const NON_NEGATIVE: bool> CategoryGraph<Id, M, Size, Cost, NON_NEGATIVE>
 where
     Id: Key,
-    M: MorphismMeta + ApplyMorphism<Size, Cost, NON_NEGATIVE>,
+    M: MorphismMeta,
     Size: Clone,
-    Cost: FloatMeasure,
+    Cost: CostMeasure,
 {
     fn new<Obj: Object<Id>>(
         category: &Category<Id, M, Obj>,
         input_size: Size,
-    ) -> CategoryGraph<Id, M, Size, Cost, NON_NEGATIVE> {
+        allows: &impl Fn(&Morphism<Id, M>) -> bool,
+    ) -> CategoryGraph<Id, M, Size, Cost, NON_NEGATIVE>
+    where
+        M: TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+    {
         let mut graph = Graph::new();
-        let (objects, morphisms, _) = category.clone().destruct();
         let mut object_id_to_index = HashMap::new();
-        let mut morphism_to_index = HashMap::new();
         let mut index_to_vertex = HashMap::new();
-        for object in objects.into_values() {
-            let index = graph.add_node(LeanVertex::Object {
+        for object in category.objects() {
+            let vertex = LeanVertex::Object {
                 inner: object.id(),
                 size: input_size.clone(),
-            });
+            };
+            let index = graph.add_node(vertex.clone());
             object_id_to_index.insert(object.id(), index);
-            index_to_vertex.insert(
-                index,
-                LeanVertex::Object {
-                    inner: object.id(),
-                    size: input_size.clone(),
-                },
-            );
+            index_to_vertex.insert(index, vertex);
         }
-        for morphism in &morphisms {
-            let index = graph.add_node(LeanVertex::Morphism {
+        for morphism in category.morphisms().filter(|m| allows(m)) {
+            let vertex = LeanVertex::Morphism {
                 inner: morphism.clone(),
                 input: input_size.clone(),
-            });
-            morphism_to_index.insert(morphism.clone(), index);
-            index_to_vertex.insert(
-                index,
-                LeanVertex::Morphism {
-                    inner: morphism.clone(),
-                    input: input_size.clone(),
-                },
-            );
-        }
-        for morphism in morphisms {
-            let index = *morphism_to_index.get(&morphism).unwrap();
+                cost: (),
+            };
+            let index = graph.add_node(vertex.clone());
+            index_to_vertex.insert(index, vertex);
+            // a morphism that fails for this input is left without edges
+            let Ok(output) = morphism.metadata.try_apply(
+                category.get_object(&morphism.source).unwrap(),
+                category.get_object(&morphism.target).unwrap(),
+                input_size.clone(),
+            ) else {
+                continue;
+            };
             graph.extend_with_edges(&[
                 (
-                    *object_id_to_index.get(&morphism.source).unwrap(),
+                    object_id_to_index[&morphism.source],
                     index,
-                    Cost::zero(),
+                    Measure(Cost::zero()),
                 ),
                 (
                     index,
-                    *object_id_to_index.get(&morphism.target).unwrap(),
-                    morphism.metadata.apply(input_size.clone()).cost,
+                    object_id_to_index[&morphism.target],
+                    Measure(output.cost),
                 ),
             ]);
         }
@@ -200,4 +591,36 @@ where
             index_to_vertex,
         }
     }
+
+    /// Runs bellman-ford from the source, and returns its index with the
+    /// predecessor of every node.
+    #[allow(clippy::type_complexity)]
+    fn bellman_ford<E>(
+        &self,
+        source: &Id,
+    ) -> Result<(NodeIndex, Vec<Option<NodeIndex>>), PathFindingError<Id, M, E>> {
+        let source_index = *self
+            .object_id_to_index
+            .get(source)
+            .ok_or(MissingObject(source.clone()))?;
+        let paths = bellman_ford(&self.graph, source_index).map_err(|_| {
+            let cycle = find_negative_cycle(&self.graph, source_index)
+                .expect("bellman_ford found a negative cycle");
+            NegativeCycle(self.morphisms_in(cycle))
+        })?;
+        Ok((source_index, paths.predecessors))
+    }
+
+    /// Selects the morphisms from a cycle of vertices in the graph
+    fn morphisms_in(&self, cycle: impl IntoIterator<Item = NodeIndex>) -> SomeVec<Morphism<Id, M>> {
+        cycle
+            .into_iter()
+            .filter_map(|index| match &self.index_to_vertex[&index] {
+                LeanVertex::Morphism { inner, .. } => Some(inner.clone()),
+                LeanVertex::Object { .. } => None,
+            })
+            .collect::<Vec<_>>()
+            .try_into()
+            .expect("every cycle contains a morphism")
+    }
 }
@@ -1,45 +1,145 @@
-use std::ops::Div;
+use std::{cmp::Reverse, ops::Div};
 
 use crate::{
     category::{Category, Key, Object},
     collections::Replace,
-    morphism::MorphismMeta,
+    cost::CostMeasure,
+    morphism::{Morphism, MorphismMeta},
+    propagation::{Accumulate, SizePropagation},
+    vertex::Vertex,
 };
 
-use super::path::WellFormedPath;
+use super::{path::WellFormedPath, query::Query};
 
-pub trait Optimizer<M, Size, Cost, const NON_NEGATIVE: bool = false>
+pub trait Optimizer<Id, M, Obj, Size, Cost, const NON_NEGATIVE: bool = false>
 where
+    Id: Key,
     M: MorphismMeta,
+    Obj: Object<Id>,
     Size: Clone,
 {
-    type Error<Id: Key, Obj>;
+    type Error;
 
-    /// Returns the cheapest path from source to target
+    /// Returns the cheapest path from source to target, where the output of
+    /// each morphism is the input of the next.
     #[allow(clippy::type_complexity)]
-    fn shortest_path<Id, Obj>(
+    fn shortest_path(
         &self,
         category: &Category<Id, M, Obj>,
         source: Id,
         target: Id,
         input_size: Size,
-    ) -> Result<Option<WellFormedPath<Id, M, Obj, Size, Cost>>, Self::Error<Id, Obj>>
+    ) -> Result<Option<WellFormedPath<Id, M, Obj, Size, Cost>>, Self::Error> {
+        self.shortest_path_propagating(category, source, target, input_size, &Accumulate)
+    }
+
+    /// Returns the cheapest path from source to target, where the input of
+    /// each morphism after the first is determined by the propagation policy.
+    /// The sizes in the returned path follow the same policy.
+    #[allow(clippy::type_complexity)]
+    fn shortest_path_propagating<P: SizePropagation<Obj, Size>>(
+        &self,
+        category: &Category<Id, M, Obj>,
+        source: Id,
+        target: Id,
+        input_size: Size,
+        propagation: &P,
+    ) -> Result<Option<WellFormedPath<Id, M, Obj, Size, Cost>>, Self::Error> {
+        self.shortest_path_filtered(category, source, target, input_size, propagation, &|_| true)
+    }
+
+    /// Like `shortest_path_propagating`, but a morphism is never used if
+    /// `allows` returns false for it. The morphisms are skipped during the
+    /// search, so the category is not copied.
+    #[allow(clippy::type_complexity)]
+    fn shortest_path_filtered<P: SizePropagation<Obj, Size>>(
+        &self,
+        category: &Category<Id, M, Obj>,
+        source: Id,
+        target: Id,
+        input_size: Size,
+        propagation: &P,
+        allows: &impl Fn(&Morphism<Id, M>) -> bool,
+    ) -> Result<Option<WellFormedPath<Id, M, Obj, Size, Cost>>, Self::Error>;
+
+    /// Returns a path that satisfies the query. The path visits each waypoint
+    /// in order, and it does not use any excluded object or morphism.
+    ///
+    /// Each leg between two consecutive stops is optimized on its own with
+    /// `shortest_path_filtered`, and the output of each leg is the input of the
+    /// next. This is greedy: once sizes accumulate, a more expensive leg that
+    /// leaves a better size for the legs after it is never considered, so the
+    /// result is only guaranteed to be the cheapest path through the stops if
+    /// every leg's cost is independent of its input. Legs are not aware of
+    /// each other, so a later leg may revisit objects of an earlier leg.
+    ///
+    /// Returns None if any leg has no path, or if a stop is excluded.
+    #[allow(clippy::type_complexity)]
+    fn shortest_path_query(
+        &self,
+        category: &Category<Id, M, Obj>,
+        query: &Query<Id, M, Size>,
+    ) -> Result<Option<WellFormedPath<Id, M, Obj, Size, Cost>>, Self::Error>
     where
-        Id: Key,
-        Obj: Object<Id>;
+        Cost: CostMeasure,
+    {
+        self.shortest_path_query_propagating(category, query, &Accumulate)
+    }
+
+    /// Like `shortest_path_query`, where the input of each morphism after the
+    /// first is determined by the propagation policy. Each leg is searched
+    /// with the size at its first stop, which is also the original size that
+    /// the policy receives for that leg.
+    #[allow(clippy::type_complexity)]
+    fn shortest_path_query_propagating<P: SizePropagation<Obj, Size>>(
+        &self,
+        category: &Category<Id, M, Obj>,
+        query: &Query<Id, M, Size>,
+        propagation: &P,
+    ) -> Result<Option<WellFormedPath<Id, M, Obj, Size, Cost>>, Self::Error>
+    where
+        Cost: CostMeasure,
+    {
+        if query.stops().any(|id| query.excluded_objects.contains(id)) {
+            return Ok(None);
+        }
+        let mut path: Option<WellFormedPath<Id, M, Obj, Size, Cost>> = None;
+        let mut input = query.input_size.clone();
+        for (source, target) in query.stops().zip(query.stops().skip(1)) {
+            if source == target {
+                continue;
+            }
+            let Some(leg) = self.shortest_path_filtered(
+                category,
+                source.clone(),
+                target.clone(),
+                input,
+                propagation,
+                &|morphism| query.allows(morphism),
+            )?
+            else {
+                return Ok(None);
+            };
+            let Vertex::Object { size, .. } = leg.vertices.last() else {
+                unreachable!("a well formed path ends with an object")
+            };
+            input = size.clone();
+            path = Some(match path {
+                None => leg,
+                Some(path) => path.join(leg),
+            });
+        }
+        Ok(path)
+    }
 
     /// Returns the cheapest path from each source to each target
     #[allow(clippy::type_complexity)]
-    fn shortest_paths<Id, Obj>(
+    fn shortest_paths(
         &self,
         category: &Category<Id, M, Obj>,
         sources: Vec<(Id, Size)>,
         targets: Vec<Id>,
-    ) -> Result<Vec<WellFormedPath<Id, M, Obj, Size, Cost>>, Self::Error<Id, Obj>>
-    where
-        Id: Key,
-        Obj: Object<Id>,
-    {
+    ) -> Result<Vec<WellFormedPath<Id, M, Obj, Size, Cost>>, Self::Error> {
         let mut results = vec![];
         for (source, input) in sources {
             for target in targets.clone() {
@@ -65,16 +165,14 @@ where
     /// - a transformation of the original Cost value with the same type.
     /// - a value with any type, as long as it implements the required trait
     ///   bounds for a cost.
-    fn ranked_paths<Id, Obj, Score, PathRet, Calculator>(
+    fn ranked_paths<Score, PathRet, Calculator>(
         &self,
         category: &Category<Id, M, Obj>,
         sources: Vec<(Id, Size)>,
         targets: Vec<Id>,
         calculate_score: Calculator,
-    ) -> Result<Vec<PathRet::With<Score>>, Self::Error<Id, Obj>>
+    ) -> Result<Vec<PathRet::With<Score>>, Self::Error>
     where
-        Id: Key,
-        Obj: Object<Id>,
         Score: Ord + Clone,
         PathRet: From<WellFormedPath<Id, M, Obj, Size, Cost>> + Replace<Cost>,
         Calculator: Fn(&PathRet) -> Score,
@@ -97,8 +195,6 @@ where
 
 /// Common score calculations for MorphismOptimizer::ranked_paths
 pub mod score {
-    use crate::vertex::Vertex;
-
     use super::*;
 
     /// This just passes along the original cost as the score
@@ -126,7 +222,26 @@ pub mod score {
         Cost: Div<Size, Output = Score> + Clone,
         Size: Clone,
     {
-        let Vertex::Morphism { input, .. } = &path.0.vertices.get(1).unwrap() else { unreachable!() };
+        let Vertex::Morphism { input, .. } = &path.0.vertices.get(1).unwrap() else {
+            unreachable!()
+        };
         path.0.cost.clone() / input.clone()
     }
+
+    /// Ranks paths by the size of their output, from largest to smallest. This
+    /// is the natural score for the MaxOutput optimizer.
+    pub fn output<Id, M, Obj, Size, Cost>(
+        path: &WellFormedPath<Id, M, Obj, Size, Cost>,
+    ) -> Reverse<Size>
+    where
+        Id: Key,
+        Obj: Object<Id>,
+        M: MorphismMeta,
+        Size: Clone,
+    {
+        let Vertex::Object { size, .. } = path.0.vertices.last() else {
+            unreachable!()
+        };
+        Reverse(size.clone())
+    }
 }
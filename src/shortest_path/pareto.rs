@@ -0,0 +1,72 @@
+use crate::{
+    category::{Category, Key, Object},
+    cost::VectorCost,
+    morphism::{Morphism, MorphismMeta, TryApplyMorphism},
+    propagation::SizePropagation,
+};
+
+use super::{labeling, my_pathfinding::PathfindingSize, path::WellFormedPath};
+
+/// Finds every path from source to target whose cost is not dominated by the
+/// cost of another path, by expanding (object, size) states with multiple
+/// labels per state.
+///
+/// A label is discarded when another label at the same state weakly dominates
+/// it, and has not visited any object that the discarded label could still
+/// reach.
+#[allow(clippy::type_complexity)]
+pub fn pareto_paths<
+    const NON_NEGATIVE: bool,
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+    Size: PathfindingSize,
+    Cost: VectorCost,
+    P: SizePropagation<Obj, Size>,
+>(
+    category: &Category<Id, M, Obj>,
+    source: Id,
+    target: Id,
+    input_size: Size,
+    propagation: &P,
+    allows: &impl Fn(&Morphism<Id, M>) -> bool,
+) -> Vec<WellFormedPath<Id, M, Obj, Size, Cost>> {
+    if source == target
+        || category.get_object(&source).is_none()
+        || category.get_object(&target).is_none()
+    {
+        return vec![];
+    }
+    let labels = labeling::search(
+        category,
+        source,
+        &target,
+        input_size,
+        Cost::zero(),
+        propagation,
+        allows,
+        |cost, _, _, step_cost| Some(cost.combine(step_cost)),
+        Cost::weakly_dominates,
+    );
+
+    // labels at different sizes of the target may still dominate each other,
+    // and labels of the same state may have equal costs
+    let candidates = labels.at(&target).collect::<Vec<_>>();
+    let mut front: Vec<usize> = vec![];
+    for &label in &candidates {
+        let cost = labels.weight(label);
+        let dominated = candidates
+            .iter()
+            .any(|&other| labels.weight(other).dominates(cost));
+        let duplicate = front
+            .iter()
+            .any(|&other| labels.weight(other).weakly_dominates(cost));
+        if !dominated && !duplicate {
+            front.push(label);
+        }
+    }
+    front
+        .into_iter()
+        .map(|label| labels.path(category, label, labels.weight(label).clone()))
+        .collect()
+}
@@ -1,26 +1,38 @@
-use std::{fmt::Display, ops::Deref};
+use std::{
+    fmt::{Debug, Display},
+    ops::Deref,
+};
 
-use pathfinding::num_traits::Zero;
-use petgraph::algo::FloatMeasure;
 use thiserror::Error;
 
 use crate::{
     category::HasId,
     collections::{Replace, SomeVec},
+    cost::CostMeasure,
     impls::Float,
-    morphism::{ApplyMorphism, Morphism, MorphismOutput},
+    morphism::{
+        ApplyMorphism, ApplyMorphismWithContext, Morphism, MorphismOutput, TryApplyMorphism,
+    },
+    propagation::{Accumulate, SizePropagation},
     vertex::Vertex,
 };
 
+use super::my_petgraph::PathFindingError;
+
 ////////////////////////////////////////
 // Path types
 //
 
 /// The basic type produced by the path optimization algorithms.
+///
+/// Each morphism vertex contains the cost of its own step, while `cost` is the
+/// total for the whole path. The total may be replaced by a score with a
+/// different type, as in `Optimizer::ranked_paths`.
 #[derive(Clone, Debug)]
-pub struct Path<Id, M, Obj = Id, Size = Float, Cost = Float> {
-    pub vertices: SomeVec<Vertex<Id, M, Obj, Size>>,
-    pub cost: Cost,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Path<Id, M, Obj = Id, Size = Float, Cost = Float, Total = Cost> {
+    pub vertices: SomeVec<Vertex<Id, M, Obj, Size, Cost>>,
+    pub cost: Total,
 }
 
 /// Privately wraps SimplePath as a promise that the contained data is
@@ -36,20 +48,21 @@ pub struct Path<Id, M, Obj = Id, Size = Float, Cost = Float> {
 /// module. Any mutation or instantiation of this type must be tightly
 /// restricted to ensure its integrity.
 #[derive(Clone, Debug)]
-pub struct WellFormedPath<Id, M, Obj = Id, Size = Float, Cost = Float>(
-    pub(super) Path<Id, M, Obj, Size, Cost>,
+pub struct WellFormedPath<Id, M, Obj = Id, Size = Float, Cost = Float, Total = Cost>(
+    pub(super) Path<Id, M, Obj, Size, Cost, Total>,
 );
 
 /// Alternate representation of a WellFormedPath with more structure:
 /// Organized as a list of each morphism from the path combined with its two
 /// adjacent objects.
 #[derive(Clone, Debug)]
-pub struct AppliedCompositeMorphism<Id, M, Obj = Id, Size = Float, Cost = Float> {
-    pub morphisms: SomeVec<AppliedMorphism<Id, M, Obj, Size>>,
-    pub cost: Cost,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AppliedCompositeMorphism<Id, M, Obj = Id, Size = Float, Cost = Float, Total = Cost> {
+    pub morphisms: SomeVec<AppliedMorphism<Id, M, Obj, Size, Cost>>,
+    pub cost: Total,
 }
 
-impl<Id, M, Obj, Size: Clone, Cost> AppliedCompositeMorphism<Id, M, Obj, Size, Cost> {
+impl<Id, M, Obj, Size: Clone, Cost, Total> AppliedCompositeMorphism<Id, M, Obj, Size, Cost, Total> {
     pub fn input(&self) -> Size {
         self.morphisms.first().source.1.clone()
     }
@@ -59,16 +72,42 @@ impl<Id, M, Obj, Size: Clone, Cost> AppliedCompositeMorphism<Id, M, Obj, Size, C
     }
 }
 
+/// An input that is divided between several paths from the same source to the
+/// same target. Each path is applied to its share of the input.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SplitRoute<Id, M, Obj = Id, Size = Float, Cost = Float> {
+    /// Each path, and the amount of the input that it receives
+    #[allow(clippy::type_complexity)]
+    pub routes: Vec<(AppliedCompositeMorphism<Id, M, Obj, Size, Cost>, Size)>,
+    /// The sum of the costs of every path
+    pub cost: Cost,
+    /// The sum of the outputs of every path
+    pub output: Size,
+}
+
+/// What is optimized when an input is split between several paths.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SplitObjective {
+    /// Minimize the sum of the costs of every path
+    MinCost,
+    /// Maximize the sum of the outputs of every path
+    MaxOutput,
+}
+
 /// A heavyweight version of Morphism that includes the full input and output
-/// objects plus their sizes since this is applied in a path.
+/// objects plus their sizes since this is applied in a path. The cost is only
+/// for this morphism.
 #[derive(Clone, Debug)]
-pub struct AppliedMorphism<Id, M, Obj = Id, Size = Float> {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AppliedMorphism<Id, M, Obj = Id, Size = Float, Cost = Float> {
     pub morphism: Morphism<Id, M>,
     pub source: (Obj, Size),
     pub target: (Obj, Size),
+    pub cost: Cost,
 }
 
-impl<Id, M, Obj, Size> Display for AppliedMorphism<Id, M, Obj, Size>
+impl<Id, M, Obj, Size, Cost> Display for AppliedMorphism<Id, M, Obj, Size, Cost>
 where
     Id: Display,
     Obj: HasId<Id>,
@@ -102,109 +141,219 @@ where
 impl<Id, M, Obj, Size, Cost> AppliedCompositeMorphism<Id, M, Obj, Size, Cost>
 where
     Size: Clone,
+    Cost: CostMeasure,
 {
+    /// Applies the same morphisms to a new input, accumulating sizes. The cost
+    /// of each morphism is recalculated, and the total is their sum.
     pub fn reapply<const NON_NEGATIVE: bool>(self, new_input: Size) -> Self
     where
-        M: ApplyMorphism<Size, Cost, NON_NEGATIVE>,
+        M: ApplyMorphismWithContext<Obj, Size, Cost, NON_NEGATIVE>,
+    {
+        self.reapply_propagating(new_input, &Accumulate)
+    }
+
+    /// Applies the same morphisms to a new input, using the propagation policy
+    /// to determine the input of each morphism after the first. The cost of
+    /// each morphism is recalculated, and the total is their sum.
+    pub fn reapply_propagating<P, const NON_NEGATIVE: bool>(
+        self,
+        new_input: Size,
+        propagation: &P,
+    ) -> Self
+    where
+        M: ApplyMorphismWithContext<Obj, Size, Cost, NON_NEGATIVE>,
+        P: SizePropagation<Obj, Size>,
+    {
+        self.try_reapply_propagating(new_input, propagation)
+            .unwrap_or_else(|e| match e.error {})
+    }
+
+    /// Like `reapply`, for morphisms that may fail to apply to their new
+    /// input. The error identifies the first morphism that failed.
+    pub fn try_reapply<const NON_NEGATIVE: bool>(
+        self,
+        new_input: Size,
+    ) -> Result<Self, ApplicationError<Id, M, M::Error>>
+    where
+        M: TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+    {
+        self.try_reapply_propagating(new_input, &Accumulate)
+    }
+
+    /// Like `reapply_propagating`, for morphisms that may fail to apply to
+    /// their new input. The error identifies the first morphism that failed.
+    pub fn try_reapply_propagating<P, const NON_NEGATIVE: bool>(
+        self,
+        new_input: Size,
+        propagation: &P,
+    ) -> Result<Self, ApplicationError<Id, M, M::Error>>
+    where
+        M: TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+        P: SizePropagation<Obj, Size>,
     {
         let mut applied_morphisms = vec![];
-        let (first, rest) = self.morphisms.destruct();
-        let mut output = first.morphism.metadata.apply(new_input.clone());
-        applied_morphisms.push(AppliedMorphism {
-            morphism: first.morphism,
-            source: (first.source.0, new_input),
-            target: (first.target.0, output.size.clone()),
-        });
-        for item in rest {
-            let input = output.size;
-            output = item.morphism.metadata.apply(input.clone());
+        let mut total = Cost::zero();
+        let mut input = new_input.clone();
+        for (step, item) in self.morphisms.into_iter().enumerate() {
+            let (source, target) = (item.source.0, item.target.0);
+            let output = match item
+                .morphism
+                .metadata
+                .try_apply(&source, &target, input.clone())
+            {
+                Ok(output) => output,
+                Err(error) => {
+                    return Err(ApplicationError {
+                        step,
+                        morphism: item.morphism,
+                        error,
+                    })
+                }
+            };
+            let next = propagation.propagate(&target, &new_input, &input, output.size);
+            total = total.plus(output.cost);
             applied_morphisms.push(AppliedMorphism {
                 morphism: item.morphism,
-                source: (item.source.0, input),
-                target: (item.target.0, output.size.clone()),
+                source: (source, input),
+                target: (target, next.clone()),
+                cost: output.cost,
             });
+            input = next;
         }
 
-        Self {
-            morphisms: applied_morphisms.try_into().expect("first() guarantees >1"),
-            cost: output.cost,
-        }
+        Ok(Self {
+            morphisms: applied_morphisms.try_into().expect("SomeVec guarantees >1"),
+            cost: total,
+        })
     }
 }
 
+/// Applies the morphisms to a new input like `reapply`, but only returns the
+/// final size and the total cost.
 impl<Id, M, Obj, Size, Cost, const NON_NEGATIVE: bool> ApplyMorphism<Size, Cost, NON_NEGATIVE>
     for AppliedCompositeMorphism<Id, M, Obj, Size, Cost>
 where
-    M: ApplyMorphism<Size, Cost, NON_NEGATIVE>,
+    M: ApplyMorphismWithContext<Obj, Size, Cost, NON_NEGATIVE>,
+    Cost: CostMeasure,
 {
     fn apply(&self, input: Size) -> MorphismOutput<Size, Cost> {
-        let mut output = self.morphisms.first().morphism.metadata.apply(input);
+        let first = self.morphisms.first();
+        let mut output =
+            first
+                .morphism
+                .metadata
+                .apply_with_context(&first.source.0, &first.target.0, input);
         for item in self.morphisms.iter_rest() {
-            output = item.morphism.metadata.apply(output.size);
+            let next = item.morphism.metadata.apply_with_context(
+                &item.source.0,
+                &item.target.0,
+                output.size,
+            );
+            output = MorphismOutput {
+                size: next.size,
+                cost: output.cost.plus(next.cost),
+            };
         }
         output
     }
 }
 
-/// Reapplies the sequence of morphisms based on a new input size. This function
-/// accumulates, which means it uses the output size of each morphism as the
-/// input size of the next.
+/// Reapplies the sequence of morphisms based on a new input size. The size of
+/// each object after the first is determined by the propagation policy from the
+/// output of the morphism before it.
 ///
-/// Use one of the sum functions to aggregate the cost into a single number.
+/// Each morphism vertex is given the cost of its own step, regardless of any
+/// cost it previously contained. The total cost of the path is also returned.
 ///
-/// The costs cannot be accumulated here because there are different traits that
-/// describe how to get a zero value depending on the optimization library. a
-/// single unified trait cannot be defined to cover both with auto-impls because
-/// there would be conflicting implementations. Specialization could solve this
-/// elegantly, but it's not stable. Alternatively you could use multiple wrapper
-/// or marker structs plus traits with associated types, but this is overly
-/// complicated. Simply summing outside the function is clearer in most cases.
+/// Each morphism is applied with the objects on either side of it, so an error
+/// is returned if a morphism is not between two objects. If a morphism fails
+/// to apply, the error identifies the first one that failed.
 #[allow(clippy::type_complexity)]
-pub(crate) fn reapply<Id, M, Obj, Size, Cost, const NON_NEGATIVE: bool>(
-    vertices: impl IntoIterator<Item = Vertex<Id, M, Obj, Size>>,
-    mut input: Size,
-) -> (Vec<Vertex<Id, M, Obj, Size>>, Vec<Cost>)
+pub(crate) fn reapply<Id, M, Obj, Size, Cost, AnyCost, P, const NON_NEGATIVE: bool>(
+    vertices: impl IntoIterator<Item = Vertex<Id, M, Obj, Size, AnyCost>>,
+    original: Size,
+    propagation: &P,
+) -> Result<(Vec<Vertex<Id, M, Obj, Size, Cost>>, Cost), PathFindingError<Id, M, M::Error>>
 where
+    Id: Debug,
     Size: Clone,
-    M: ApplyMorphism<Size, Cost, NON_NEGATIVE>,
+    Cost: CostMeasure,
+    M: TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+    P: SizePropagation<Obj, Size>,
 {
+    let vertices = vertices.into_iter().collect::<Vec<_>>();
+    // the morphisms are applied while the objects can still be borrowed, before
+    // the vertices are moved into the new path.
+    let mut input = original.clone();
+    let mut steps = vec![];
+    for (i, vertex) in vertices.iter().enumerate() {
+        let Vertex::Morphism { inner, .. } = vertex else {
+            continue;
+        };
+        let (
+            Some(Vertex::Object { inner: source, .. }),
+            Some(Vertex::Object { inner: target, .. }),
+        ) = (vertices.get(i.wrapping_sub(1)), vertices.get(i + 1))
+        else {
+            let error = if i == 0 {
+                InvalidPath::SourceIsNotObject
+            } else if !matches!(vertices[i - 1], Vertex::Object { .. }) {
+                invalid_object_at(i - 1, vertices.len())
+            } else if i + 1 == vertices.len() {
+                InvalidPath::TargetIsNotObject
+            } else {
+                invalid_object_at(i + 1, vertices.len())
+            };
+            return Err(error.into());
+        };
+        let MorphismOutput { size, cost } =
+            match inner.metadata.try_apply(source, target, input.clone()) {
+                Ok(output) => output,
+                Err(error) => {
+                    let step = steps.len();
+                    let morphism = vertices
+                        .into_iter()
+                        .filter_map(|vertex| match vertex {
+                            Vertex::Morphism { inner, .. } => Some(inner),
+                            Vertex::Object { .. } => None,
+                        })
+                        .nth(step)
+                        .expect("the failed morphism is in the path");
+                    return Err(PathFindingError::ApplicationFailed(ApplicationError {
+                        step,
+                        morphism,
+                        error,
+                    }));
+                }
+            };
+        let next = propagation.propagate(target, &original, &input, size);
+        steps.push((input, cost));
+        input = next;
+    }
+    let mut sizes = steps
+        .iter()
+        .map(|(size, _)| size.clone())
+        .chain([input])
+        .collect::<Vec<_>>()
+        .into_iter();
+    let mut steps = steps.into_iter();
+
     let mut new_path = vec![];
-    let mut cost_agg = vec![];
+    let mut total = Cost::zero();
     for vertex in vertices {
         match vertex {
             Vertex::Object { inner, .. } => new_path.push(Vertex::Object {
                 inner,
-                size: input.clone(),
+                size: sizes.next().expect("one more object than morphisms"),
             }),
             Vertex::Morphism { inner, .. } => {
-                let MorphismOutput { size, cost } = inner.metadata.apply(input.clone());
-                new_path.push(Vertex::Morphism {
-                    inner,
-                    input: input.clone(),
-                });
-                input = size;
-                cost_agg.push(cost);
+                let (input, cost) = steps.next().expect("one step per morphism");
+                new_path.push(Vertex::Morphism { inner, input, cost });
+                total = total.plus(cost);
             }
         }
     }
-    (new_path, cost_agg)
-}
-
-#[allow(unused)]
-pub(crate) fn sum_zero<T: Zero>(ns: impl IntoIterator<Item = T>) -> T {
-    let mut agg = T::zero();
-    for n in ns {
-        agg = n + agg;
-    }
-    agg
-}
-
-pub(crate) fn sum_petgraph<T: FloatMeasure>(ns: impl IntoIterator<Item = T>) -> T {
-    let mut agg = T::zero();
-    for n in ns {
-        agg = n + agg;
-    }
-    agg
+    Ok((new_path, total))
 }
 
 ////////////////////////////////////////
@@ -213,36 +362,129 @@ pub(crate) fn sum_petgraph<T: FloatMeasure>(ns: impl IntoIterator<Item = T>) ->
 
 // Do not implement DerefMut. That would defeat the entire purpose of
 // WellFormedPath. Use Into<Path> to make changes.
-impl<Id, M, Obj, Size, Cost> Deref for WellFormedPath<Id, M, Obj, Size, Cost> {
-    type Target = Path<Id, M, Obj, Size, Cost>;
+impl<Id, M, Obj, Size, Cost, Total> Deref for WellFormedPath<Id, M, Obj, Size, Cost, Total> {
+    type Target = Path<Id, M, Obj, Size, Cost, Total>;
     fn deref(&self) -> &Self::Target {
         &self.0
     }
 }
 
-impl<Id, M, Obj, Size, Cost> WellFormedPath<Id, M, Obj, Size, Cost> {
-    pub fn into_inner(self) -> Path<Id, M, Obj, Size, Cost> {
+impl<Id, M, Obj, Size, Cost, Total> WellFormedPath<Id, M, Obj, Size, Cost, Total> {
+    pub fn into_inner(self) -> Path<Id, M, Obj, Size, Cost, Total> {
         self.0
     }
+
+    /// Appends a path that starts at the object where this path ends. The
+    /// first object of `next` is dropped in favor of the last object of this
+    /// path, and the totals are summed.
+    pub(super) fn join(self, next: Self) -> Self
+    where
+        Total: CostMeasure,
+    {
+        let mut vertices = self.0.vertices.to_vec();
+        vertices.extend(next.0.vertices.into_iter().skip(1));
+        WellFormedPath(Path {
+            vertices: vertices.try_into().expect("not empty"),
+            cost: self.0.cost.plus(next.0.cost),
+        })
+    }
 }
 
-impl<Id, M, Obj, Size, Cost> From<WellFormedPath<Id, M, Obj, Size, Cost>>
-    for Path<Id, M, Obj, Size, Cost>
+/// Verifies that the path is in object/morphism alternating form, starting and
+/// ending with objects, and that each morphism goes from the object before it
+/// to the object after it.
+impl<Id, M, Obj, Size, Cost, Total> TryFrom<Path<Id, M, Obj, Size, Cost, Total>>
+    for WellFormedPath<Id, M, Obj, Size, Cost, Total>
+where
+    Id: PartialEq,
+    Obj: HasId<Id>,
+{
+    type Error = InvalidPath;
+
+    fn try_from(value: Path<Id, M, Obj, Size, Cost, Total>) -> Result<Self, Self::Error> {
+        let len = value.vertices.len();
+        if len < 3 || len % 2 != 1 {
+            return Err(InvalidPath::MalformedStructure);
+        }
+        for (i, vertex) in value.vertices.iter().enumerate() {
+            match (i % 2, vertex) {
+                (0, Vertex::Object { .. }) => (),
+                (1, Vertex::Morphism { inner, .. }) => {
+                    let id_at = |j: usize| match value.vertices.get(j) {
+                        Some(Vertex::Object { inner, .. }) => Some(inner.id()),
+                        _ => None,
+                    };
+                    // a vertex that is not an object is reported when it is
+                    // reached
+                    let connects = |j, id: &Id| id_at(j).is_none_or(|object| &object == id);
+                    if !connects(i - 1, &inner.source) || !connects(i + 1, &inner.target) {
+                        return Err(InvalidPath::DisconnectedMorphism(i / 2));
+                    }
+                }
+                (1, _) => return Err(InvalidPath::InnerIsNotMorphism),
+                _ => return Err(invalid_object_at(i, len)),
+            }
+        }
+        Ok(WellFormedPath(value))
+    }
+}
+
+/// The error for a vertex at an even index that is not an object
+fn invalid_object_at(index: usize, len: usize) -> InvalidPath {
+    if index == 0 {
+        InvalidPath::SourceIsNotObject
+    } else if index == len - 1 {
+        InvalidPath::TargetIsNotObject
+    } else {
+        InvalidPath::InnerIsNotObject
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<Id, M, Obj, Size, Cost, Total> serde::Serialize
+    for WellFormedPath<Id, M, Obj, Size, Cost, Total>
+where
+    Path<Id, M, Obj, Size, Cost, Total>: serde::Serialize,
 {
-    fn from(value: WellFormedPath<Id, M, Obj, Size, Cost>) -> Self {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+/// Deserializes a Path and verifies its structure with TryFrom.
+#[cfg(feature = "serde")]
+impl<'de, Id, M, Obj, Size, Cost, Total> serde::Deserialize<'de>
+    for WellFormedPath<Id, M, Obj, Size, Cost, Total>
+where
+    Path<Id, M, Obj, Size, Cost, Total>: serde::Deserialize<'de>,
+    Id: PartialEq,
+    Obj: HasId<Id>,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Path::deserialize(deserializer)?
+            .try_into()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl<Id, M, Obj, Size, Cost, Total> From<WellFormedPath<Id, M, Obj, Size, Cost, Total>>
+    for Path<Id, M, Obj, Size, Cost, Total>
+{
+    fn from(value: WellFormedPath<Id, M, Obj, Size, Cost, Total>) -> Self {
         value.0
     }
 }
 
-impl<Id, M, Obj, Size, Cost> From<WellFormedPath<Id, M, Obj, Size, Cost>>
-    for AppliedCompositeMorphism<Id, M, Obj, Size, Cost>
+impl<Id, M, Obj, Size, Cost, Total> From<WellFormedPath<Id, M, Obj, Size, Cost, Total>>
+    for AppliedCompositeMorphism<Id, M, Obj, Size, Cost, Total>
 where
     Id: Clone,
     Obj: Clone,
     M: Clone,
     Size: Clone,
+    Cost: Clone,
 {
-    fn from(value: WellFormedPath<Id, M, Obj, Size, Cost>) -> Self {
+    fn from(value: WellFormedPath<Id, M, Obj, Size, Cost, Total>) -> Self {
         value
             .0
             .try_into()
@@ -250,13 +492,14 @@ where
     }
 }
 
-impl<Id, M, Obj, Size, Cost> Display for AppliedCompositeMorphism<Id, M, Obj, Size, Cost>
+impl<Id, M, Obj, Size, Cost, Total> Display
+    for AppliedCompositeMorphism<Id, M, Obj, Size, Cost, Total>
 where
     Id: Display,
     Obj: HasId<Id>,
     M: Display,
     Size: Display,
-    Cost: Display,
+    Total: Display,
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         Display::fmt(&self.cost, f)?;
@@ -265,17 +508,18 @@ where
     }
 }
 
-impl<Id, M, Obj, Size, Cost> TryFrom<Path<Id, M, Obj, Size, Cost>>
-    for AppliedCompositeMorphism<Id, M, Obj, Size, Cost>
+impl<Id, M, Obj, Size, Cost, Total> TryFrom<Path<Id, M, Obj, Size, Cost, Total>>
+    for AppliedCompositeMorphism<Id, M, Obj, Size, Cost, Total>
 where
     Id: Clone,
     Obj: Clone,
     M: Clone,
     Size: Clone,
+    Cost: Clone,
 {
     type Error = InvalidPath;
 
-    fn try_from(value: Path<Id, M, Obj, Size, Cost>) -> Result<Self, Self::Error> {
+    fn try_from(value: Path<Id, M, Obj, Size, Cost, Total>) -> Result<Self, Self::Error> {
         let len = value.vertices.len();
         if len == 0 {
             return Err(InvalidPath::EmptyPath);
@@ -289,24 +533,29 @@ where
             .chunks(2)
             .collect::<Vec<_>>()
             .windows(2)
-            .map(|window| {
+            .enumerate()
+            .map(|(step, window)| {
                 let [this, that] = window else { unreachable!() };
-                let [source, morphism] = this else { unreachable!() };
+                let [source, morphism] = this else {
+                    unreachable!()
+                };
                 let target = &that[0];
                 let source = match source {
                     Vertex::Object { inner, size } => (inner.clone(), size.clone()),
-                    _ => return Err(InvalidPath::SourceIsNotObject),
+                    _ => return Err(invalid_object_at(2 * step, len)),
                 };
                 let target = match target {
                     Vertex::Object { inner, size } => (inner.clone(), size.clone()),
-                    _ => return Err(InvalidPath::TargetIsNotObject),
+                    _ => return Err(invalid_object_at(2 * step + 2, len)),
+                };
+                let Vertex::Morphism { inner, cost, .. } = morphism.clone() else {
+                    return Err(InvalidPath::InnerIsNotMorphism);
                 };
-                let Vertex::Morphism { inner, .. } = morphism.clone()
-                    else { return Err(InvalidPath::InnerIsNotMorphism)};
                 Ok(AppliedMorphism {
                     morphism: inner,
                     source,
                     target,
+                    cost,
                 })
             })
             .collect::<Result<Vec<_>, InvalidPath>>()?;
@@ -318,14 +567,16 @@ where
     }
 }
 
-impl<Id, M, Obj, Size, Cost> Replace<Cost> for AppliedCompositeMorphism<Id, M, Obj, Size, Cost> {
-    type With<U> = AppliedCompositeMorphism<Id, M, Obj, Size, U>;
+impl<Id, M, Obj, Size, Cost, Total> Replace<Total>
+    for AppliedCompositeMorphism<Id, M, Obj, Size, Cost, Total>
+{
+    type With<U> = AppliedCompositeMorphism<Id, M, Obj, Size, Cost, U>;
 
-    fn read(&self) -> &Cost {
+    fn read(&self) -> &Total {
         &self.cost
     }
 
-    fn replace<R>(self, item: R) -> (Self::With<R>, Cost) {
+    fn replace<R>(self, item: R) -> (Self::With<R>, Total) {
         (
             AppliedCompositeMorphism {
                 morphisms: self.morphisms,
@@ -336,14 +587,14 @@ impl<Id, M, Obj, Size, Cost> Replace<Cost> for AppliedCompositeMorphism<Id, M, O
     }
 }
 
-impl<Id, M, Obj, Size, Cost> Replace<Cost> for Path<Id, M, Obj, Size, Cost> {
-    type With<U> = Path<Id, M, Obj, Size, U>;
+impl<Id, M, Obj, Size, Cost, Total> Replace<Total> for Path<Id, M, Obj, Size, Cost, Total> {
+    type With<U> = Path<Id, M, Obj, Size, Cost, U>;
 
-    fn read(&self) -> &Cost {
+    fn read(&self) -> &Total {
         &self.cost
     }
 
-    fn replace<R>(self, item: R) -> (Self::With<R>, Cost) {
+    fn replace<R>(self, item: R) -> (Self::With<R>, Total) {
         (
             Path {
                 vertices: self.vertices,
@@ -354,14 +605,16 @@ impl<Id, M, Obj, Size, Cost> Replace<Cost> for Path<Id, M, Obj, Size, Cost> {
     }
 }
 
-impl<Id, M, Obj, Size, Cost> Replace<Cost> for WellFormedPath<Id, M, Obj, Size, Cost> {
-    type With<U> = WellFormedPath<Id, M, Obj, Size, U>;
+impl<Id, M, Obj, Size, Cost, Total> Replace<Total>
+    for WellFormedPath<Id, M, Obj, Size, Cost, Total>
+{
+    type With<U> = WellFormedPath<Id, M, Obj, Size, Cost, U>;
 
-    fn read(&self) -> &Cost {
+    fn read(&self) -> &Total {
         &self.0.cost
     }
 
-    fn replace<R>(self, item: R) -> (Self::With<R>, Cost) {
+    fn replace<R>(self, item: R) -> (Self::With<R>, Total) {
         let (one, two) = self.0.replace(item);
         (WellFormedPath(one), two)
     }
@@ -371,16 +624,36 @@ impl<Id, M, Obj, Size, Cost> Replace<Cost> for WellFormedPath<Id, M, Obj, Size,
 // Errors
 //
 
+/// A morphism in a path could not be applied to its input.
+#[derive(Error, Debug)]
+#[error("Morphism {step} of the path could not be applied to its input")]
+pub struct ApplicationError<Id, M, E> {
+    /// The position of the morphism in the path, starting from zero
+    pub step: usize,
+    pub morphism: Morphism<Id, M>,
+    /// The error returned by the morphism
+    pub error: E,
+}
+
 #[derive(Error, Debug)]
 pub enum InvalidPath {
     #[error("The source vertex of this path is not an object")]
     SourceIsNotObject,
-    #[error("")]
+    #[error("Every vertex between two objects in this path must be a morphism")]
     InnerIsNotMorphism,
     #[error("The target vertex of this path is not an object")]
     TargetIsNotObject,
+    #[error("Every vertex between two morphisms in this path must be an object")]
+    InnerIsNotObject,
+    /// Contains the position of the morphism in the path, starting from zero
+    #[error(
+        "Morphism {0} of this path does not go from the object before it to the object after it"
+    )]
+    DisconnectedMorphism(usize),
     #[error("The lack of a path should be represented with None")]
     EmptyPath,
-    #[error("")]
+    #[error(
+        "The path must alternate between objects and morphisms, starting and ending with an object"
+    )]
     MalformedStructure,
 }
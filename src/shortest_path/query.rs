@@ -0,0 +1,73 @@
+use std::collections::HashSet;
+
+use crate::{category::Key, impls::Float, morphism::Morphism};
+
+/// A request for a path with constraints that only apply to this search. It is
+/// accepted by every optimizer with `Optimizer::shortest_path_query`.
+///
+/// The category is neither modified nor copied. The optimizer skips the
+/// morphisms that the query does not allow while it searches. The exclusions
+/// can also be given to any search that accepts an `allows` predicate, such as
+/// `KShortest::k_shortest_paths_filtered`, with `|m| query.allows(m)`.
+pub struct Query<'a, Id, M, Size = Float> {
+    pub source: Id,
+    pub target: Id,
+    pub input_size: Size,
+    /// Objects that the path must visit in this order, between the source and
+    /// the target
+    pub waypoints: Vec<Id>,
+    /// Objects that the path must not visit
+    pub excluded_objects: HashSet<Id>,
+    /// A morphism is not used if any of these return true
+    #[allow(clippy::type_complexity)]
+    pub excluded_morphisms: Vec<Box<dyn Fn(&Morphism<Id, M>) -> bool + 'a>>,
+}
+
+impl<'a, Id: Key, M, Size> Query<'a, Id, M, Size> {
+    /// A query without any constraints, which is equivalent to
+    /// `Optimizer::shortest_path`.
+    pub fn new(source: Id, target: Id, input_size: Size) -> Self {
+        Self {
+            source,
+            target,
+            input_size,
+            waypoints: vec![],
+            excluded_objects: HashSet::new(),
+            excluded_morphisms: vec![],
+        }
+    }
+
+    /// Adds a waypoint after any existing waypoints.
+    pub fn via(mut self, waypoint: Id) -> Self {
+        self.waypoints.push(waypoint);
+        self
+    }
+
+    pub fn avoiding(mut self, object: Id) -> Self {
+        self.excluded_objects.insert(object);
+        self
+    }
+
+    /// Excludes every morphism for which the predicate returns true, such as
+    /// a family of morphisms with the same kind of metadata.
+    pub fn excluding(mut self, predicate: impl Fn(&Morphism<Id, M>) -> bool + 'a) -> Self {
+        self.excluded_morphisms.push(Box::new(predicate));
+        self
+    }
+
+    /// The source, each waypoint, and the target, in order
+    pub fn stops(&self) -> impl Iterator<Item = &Id> {
+        std::iter::once(&self.source)
+            .chain(&self.waypoints)
+            .chain(std::iter::once(&self.target))
+    }
+
+    pub fn allows(&self, morphism: &Morphism<Id, M>) -> bool {
+        !self.excluded_objects.contains(&morphism.source)
+            && !self.excluded_objects.contains(&morphism.target)
+            && !self
+                .excluded_morphisms
+                .iter()
+                .any(|excluded| excluded(morphism))
+    }
+}
@@ -0,0 +1,69 @@
+use crate::{
+    category::{Category, Key, Object},
+    cost::{CostMeasure, VectorCost},
+    morphism::{ConsumeResources, Morphism, MorphismMeta, TryApplyMorphism},
+    propagation::SizePropagation,
+};
+
+use super::{labeling, my_pathfinding::PathfindingSize, path::WellFormedPath};
+
+/// Finds the cheapest path from source to target whose total resources do not
+/// exceed `limits` in any component, by expanding (object, size) states with
+/// multiple labels per state.
+///
+/// A label is discarded when another label at the same state has a cost and
+/// resources that are no greater, and has not visited any object that the
+/// discarded label could still reach.
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+pub fn shortest_single_path_with_resource_limits<
+    const NON_NEGATIVE: bool,
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE> + ConsumeResources<Obj, Size, R>,
+    Size: PathfindingSize,
+    Cost: CostMeasure,
+    R: VectorCost,
+    P: SizePropagation<Obj, Size>,
+>(
+    category: &Category<Id, M, Obj>,
+    source: Id,
+    target: Id,
+    input_size: Size,
+    limits: &R,
+    propagation: &P,
+    allows: &impl Fn(&Morphism<Id, M>) -> bool,
+) -> Option<WellFormedPath<Id, M, Obj, Size, Cost>> {
+    if source == target
+        || category.get_object(&source).is_none()
+        || category.get_object(&target).is_none()
+    {
+        return None;
+    }
+    let labels = labeling::search(
+        category,
+        source,
+        &target,
+        input_size,
+        (Cost::zero(), R::zero()),
+        propagation,
+        allows,
+        |(cost, resources), morphism, size, step_cost| {
+            let resources = resources.combine(&morphism.metadata.resources(
+                category.get_object(&morphism.source).unwrap(),
+                category.get_object(&morphism.target).unwrap(),
+                size,
+            ));
+            resources
+                .weakly_dominates(limits)
+                .then(|| (cost.plus(*step_cost), resources))
+        },
+        |(cost, resources), (other_cost, other_resources)| {
+            cost <= other_cost && resources.weakly_dominates(other_resources)
+        },
+    );
+
+    let best = labels
+        .at(&target)
+        .min_by_key(|&label| labels.weight(label).0)?;
+    Some(labels.path(category, best, labels.weight(best).0))
+}
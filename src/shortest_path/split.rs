@@ -0,0 +1,141 @@
+use std::{
+    collections::HashSet,
+    ops::{Add, Div},
+};
+
+use crate::{
+    category::{Category, Key, Object},
+    cost::CostMeasure,
+    morphism::{Morphism, MorphismMeta, TryApplyMorphism},
+    propagation::SizePropagation,
+};
+
+use super::{
+    my_pathfinding::{shortest_single_path_with_dijkstra, PathfindingSize},
+    path::{AppliedCompositeMorphism, SplitObjective, SplitRoute},
+};
+
+/// Divides the input into `chunks` equal parts, and allocates them one at a
+/// time to whichever of up to `n_paths` candidate paths improves the objective
+/// the most when it receives one more chunk. Each candidate is the cheapest
+/// path for a single chunk that shares no morphism with an earlier candidate,
+/// so every morphism is applied to the whole input that passes through it.
+///
+/// This finds the optimal split when the cost of each path is convex in its
+/// input, or equivalently when its output is concave, up to the precision of
+/// the chunk size.
+///
+/// Returns None if there is no path, or if a chunk cannot be allocated to any
+/// path without a morphism failing to apply.
+#[allow(clippy::too_many_arguments)]
+pub fn split_route<
+    Id: Key,
+    Obj: Object<Id>,
+    M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, true>,
+    Size: PathfindingSize + Ord + Add<Output = Size> + Div<Output = Size> + From<u32>,
+    Cost: CostMeasure,
+    P: SizePropagation<Obj, Size>,
+>(
+    category: &Category<Id, M, Obj>,
+    source: Id,
+    target: Id,
+    input_size: Size,
+    n_paths: usize,
+    chunks: u32,
+    objective: SplitObjective,
+    propagation: &P,
+    allows: &impl Fn(&Morphism<Id, M>) -> bool,
+) -> Option<SplitRoute<Id, M, Obj, Size, Cost>> {
+    if chunks == 0 {
+        return None;
+    }
+    let chunk = input_size.clone() / Size::from(chunks);
+    let mut used = HashSet::new();
+    let mut candidates: Vec<AppliedCompositeMorphism<Id, M, Obj, Size, Cost>> = vec![];
+    while candidates.len() < n_paths {
+        let Some(path) = shortest_single_path_with_dijkstra(
+            category,
+            source.clone(),
+            target.clone(),
+            chunk.clone(),
+            propagation,
+            &|morphism| allows(morphism) && !used.contains(&morphism.key()),
+        ) else {
+            break;
+        };
+        let path: AppliedCompositeMorphism<Id, M, Obj, Size, Cost> = path.into();
+        used.extend(path.morphisms.iter().map(|item| item.morphism.key()));
+        candidates.push(path);
+    }
+    if candidates.is_empty() {
+        return None;
+    }
+
+    let mut allocated: Vec<Option<(Size, _)>> = vec![None; candidates.len()];
+    for _ in 0..chunks {
+        let mut best = None;
+        for (i, candidate) in candidates.iter().enumerate() {
+            let size = match &allocated[i] {
+                None => chunk.clone(),
+                Some((size, _)) => size.clone() + chunk.clone(),
+            };
+            let Ok(applied) = candidate
+                .clone()
+                .try_reapply_propagating(size.clone(), propagation)
+            else {
+                continue;
+            };
+            let (cost, output) = totals(allocated.iter().enumerate().filter_map(|(j, other)| {
+                if j == i {
+                    Some(&applied)
+                } else {
+                    other.as_ref().map(|(_, other)| other)
+                }
+            }));
+            let better = match (&best, objective) {
+                (None, _) => true,
+                (Some((_, _, _, best_cost, _)), SplitObjective::MinCost) => cost < *best_cost,
+                (Some((_, _, _, _, best_output)), SplitObjective::MaxOutput) => {
+                    output > *best_output
+                }
+            };
+            if better {
+                best = Some((i, size, applied, cost, output));
+            }
+        }
+        let (i, size, applied, _, _) = best?;
+        allocated[i] = Some((size, applied));
+    }
+
+    let (cost, output) = totals(allocated.iter().flatten().map(|(_, applied)| applied));
+    let routes = allocated
+        .into_iter()
+        .flatten()
+        .map(|(size, applied)| (applied, size))
+        .collect();
+    Some(SplitRoute {
+        routes,
+        cost,
+        output,
+    })
+}
+
+/// The total cost and output of the paths
+fn totals<'a, Id: 'a, M: 'a, Obj: 'a, Size, Cost>(
+    paths: impl IntoIterator<Item = &'a AppliedCompositeMorphism<Id, M, Obj, Size, Cost>>,
+) -> (Cost, Size)
+where
+    Size: Clone + Add<Output = Size> + 'a,
+    Cost: CostMeasure + 'a,
+{
+    let mut cost = Cost::zero();
+    let mut output: Option<Size> = None;
+    for path in paths {
+        cost = cost.plus(path.cost);
+        output = Some(match output {
+            None => path.output(),
+            Some(output) => output + path.output(),
+        });
+    }
+    (cost, output.expect("at least one path is allocated"))
+}
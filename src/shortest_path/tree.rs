@@ -0,0 +1,230 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::{
+    category::{Category, Key, Object},
+    cost::CostMeasure,
+    impls::Float,
+    morphism::{Morphism, MorphismMeta, TryApplyMorphism},
+    propagation::SizePropagation,
+    vertex::Vertex,
+};
+
+use super::{
+    my_pathfinding::SettledNode,
+    path::{ApplicationError, Path, WellFormedPath},
+};
+
+/// The cheapest path from one source to every object that it can reach, found
+/// by a single search. Each path is extracted with `path_to`.
+///
+/// Sizes and costs are accumulated from the source along each path, so every
+/// path is the same as if it were reapplied with the propagation policy that
+/// built the tree. The path to an object may pass through another object at a
+/// different size than the path to that object, so the paths are stored as a
+/// tree of (object, size) states rather than a tree of objects.
+#[derive(Clone, Debug)]
+pub struct ShortestPathTree<Id, M, Obj = Id, Size = Float, Cost = Float> {
+    source: Id,
+    nodes: Vec<Node<Id, M, Obj, Size, Cost>>,
+    /// The node at the end of the path to each object
+    objects: HashMap<Id, usize>,
+}
+
+#[derive(Clone, Debug)]
+struct Node<Id, M, Obj, Size, Cost> {
+    object: Obj,
+    size: Size,
+    /// The total cost of the path from the source
+    cost: Cost,
+    /// The previous node, with the morphism that leads from it and the cost of
+    /// that morphism alone. None for the source.
+    predecessor: Option<(usize, Morphism<Id, M>, Cost)>,
+}
+
+impl<Id, M, Obj, Size, Cost> ShortestPathTree<Id, M, Obj, Size, Cost>
+where
+    Id: Key,
+    M: Clone,
+    Obj: Clone,
+    Size: Clone,
+    Cost: CostMeasure,
+{
+    pub fn source(&self) -> &Id {
+        &self.source
+    }
+
+    /// Every object with a path from the source, not including the source.
+    pub fn reachable(&self) -> impl Iterator<Item = &Id> {
+        self.objects.keys().filter(|id| *id != &self.source)
+    }
+
+    /// The node at the end of the path to the target, or None if there is no
+    /// path.
+    fn node(&self, target: &Id) -> Option<&Node<Id, M, Obj, Size, Cost>> {
+        self.objects
+            .get(target)
+            .map(|&node| &self.nodes[node])
+            .filter(|node| node.predecessor.is_some())
+    }
+
+    /// The total cost of the path to the target, or None if there is no path.
+    pub fn cost_to(&self, target: &Id) -> Option<Cost> {
+        self.node(target).map(|node| node.cost)
+    }
+
+    /// The size of the target at the end of its path, or None if there is no
+    /// path.
+    pub fn size_at(&self, target: &Id) -> Option<&Size> {
+        self.node(target).map(|node| &node.size)
+    }
+
+    /// The cheapest path from the source to the target, or None if there is
+    /// no path or the target is the source.
+    pub fn path_to(&self, target: &Id) -> Option<WellFormedPath<Id, M, Obj, Size, Cost>> {
+        let mut current = self.node(target)?;
+        let total = current.cost;
+        let mut vertices = vec![];
+        loop {
+            vertices.push(Vertex::Object {
+                inner: current.object.clone(),
+                size: current.size.clone(),
+            });
+            let Some((previous, morphism, cost)) = &current.predecessor else {
+                break;
+            };
+            current = &self.nodes[*previous];
+            vertices.push(Vertex::Morphism {
+                inner: morphism.clone(),
+                input: current.size.clone(),
+                cost: *cost,
+            });
+        }
+        vertices.reverse();
+        Some(WellFormedPath(Path {
+            vertices: vertices.try_into().expect("contains at least the target"),
+            cost: total,
+        }))
+    }
+
+    /// Builds the tree by applying the predecessor of each object, starting
+    /// from the source. Objects that cannot be reached from the source through
+    /// their predecessors are left out.
+    ///
+    /// Returns an error if a morphism cannot be applied to the size that it
+    /// receives in the tree, where `step` is its position in the path.
+    #[allow(clippy::type_complexity)]
+    pub(super) fn from_predecessors<const NON_NEGATIVE: bool, P>(
+        category: &Category<Id, M, Obj>,
+        source: Id,
+        input_size: Size,
+        predecessors: impl IntoIterator<Item = Morphism<Id, M>>,
+        propagation: &P,
+    ) -> Result<Self, ApplicationError<Id, M, M::Error>>
+    where
+        M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
+        Obj: Object<Id>,
+        P: SizePropagation<Obj, Size>,
+    {
+        let mut children: HashMap<Id, Vec<Morphism<Id, M>>> = HashMap::new();
+        for morphism in predecessors {
+            children
+                .entry(morphism.source.clone())
+                .or_default()
+                .push(morphism);
+        }
+        let mut nodes = vec![Node {
+            object: category.get_object(&source).unwrap().clone(),
+            size: input_size.clone(),
+            cost: Cost::zero(),
+            predecessor: None,
+        }];
+        let mut objects = HashMap::from([(source.clone(), 0)]);
+        let mut queue = VecDeque::from([(source.clone(), 0)]);
+        while let Some((id, step)) = queue.pop_front() {
+            for morphism in children.remove(&id).into_iter().flatten() {
+                let previous = objects[&id];
+                let parent = &nodes[previous];
+                let target = category.get_object(&morphism.target).unwrap().clone();
+                let output = morphism
+                    .metadata
+                    .try_apply(&parent.object, &target, parent.size.clone())
+                    .map_err(|error| ApplicationError {
+                        step,
+                        morphism: morphism.clone(),
+                        error,
+                    })?;
+                let size = propagation.propagate(&target, &input_size, &parent.size, output.size);
+                let node = Node {
+                    object: target,
+                    size,
+                    cost: parent.cost.plus(output.cost),
+                    predecessor: Some((previous, morphism.clone(), output.cost)),
+                };
+                objects.insert(morphism.target.clone(), nodes.len());
+                nodes.push(node);
+                queue.push_back((morphism.target, step + 1));
+            }
+        }
+        Ok(Self {
+            source,
+            nodes,
+            objects,
+        })
+    }
+
+    /// Builds the tree from the states of a search, where `objects` is the
+    /// state at the end of the path to each object. The states that are not
+    /// on any of those paths are left out.
+    pub(super) fn from_states(
+        category: &Category<Id, M, Obj>,
+        states: Vec<SettledNode<Id, M, Size, Cost>>,
+        objects: HashMap<Id, usize>,
+    ) -> Self
+    where
+        M: MorphismMeta,
+        Obj: Object<Id>,
+    {
+        let mut kept = vec![false; states.len()];
+        for &node in objects.values() {
+            let mut current = Some(node);
+            while let Some(node) = current.filter(|&node| !kept[node]) {
+                kept[node] = true;
+                current = states[node].1.as_ref().map(|(previous, _, _)| *previous);
+            }
+        }
+        // a state is always found after the state before it, so the previous
+        // node has already been added
+        let mut positions = vec![0; states.len()];
+        let mut nodes: Vec<Node<Id, M, Obj, Size, Cost>> = vec![];
+        let mut source = None;
+        for (index, ((id, size), predecessor)) in states.into_iter().enumerate() {
+            if !kept[index] {
+                continue;
+            }
+            let predecessor =
+                predecessor.map(|(previous, morphism, cost)| (positions[previous], morphism, cost));
+            let cost = match &predecessor {
+                Some((previous, _, step_cost)) => nodes[*previous].cost.plus(*step_cost),
+                None => Cost::zero(),
+            };
+            if predecessor.is_none() {
+                source = Some(id.clone());
+            }
+            positions[index] = nodes.len();
+            nodes.push(Node {
+                object: category.get_object(&id).unwrap().clone(),
+                size,
+                cost,
+                predecessor,
+            });
+        }
+        Self {
+            source: source.expect("the search starts at the source"),
+            nodes,
+            objects: objects
+                .into_iter()
+                .map(|(id, node)| (id, positions[node]))
+                .collect(),
+        }
+    }
+}
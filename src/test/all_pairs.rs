@@ -0,0 +1,204 @@
+use std::collections::HashSet;
+
+use crate::category::Category;
+use crate::shortest_path::optimizer::Optimizer;
+use crate::shortest_path::*;
+
+use super::{quote_names as names, Quote};
+
+fn category(quotes: Vec<(u8, u8, &'static str, i64, i64)>) -> Category<u8, Quote> {
+    let mut category = super::quotes(quotes);
+    category.add_object(6).unwrap();
+    category
+}
+
+/// Negative costs without a negative cycle. 6 is not connected to anything.
+fn tokens() -> Category<u8, Quote> {
+    category(vec![
+        (0, 1, "a", 2, 4),
+        (0, 2, "b", 1, 7),
+        (1, 2, "c", 1, -2),
+        (2, 1, "d", 1, 3),
+        (1, 3, "e", 3, 5),
+        (2, 3, "f", 1, 1),
+        (3, 4, "g", 1, -1),
+        (2, 4, "h", 1, 6),
+        (4, 0, "i", 1, 2),
+        (4, 5, "j", 1, 3),
+        (5, 3, "k", 1, -1),
+        (1, 1, "l", 1, 1),
+    ])
+}
+
+#[test]
+fn every_pair_matches_negatable() {
+    let category = tokens();
+    let floyd_warshall = FloydWarshall.all_pairs(&category, 10);
+    let johnson = Johnson.all_pairs(&category, 10);
+
+    for source in 0..7 {
+        for target in 0..7 {
+            let expected = Negatable
+                .shortest_path(&category, source, target, 10)
+                .unwrap()
+                .map(names);
+            let pair = format!("{source} -> {target}");
+            assert_eq!(
+                floyd_warshall.path(&source, &target).unwrap().map(names),
+                expected,
+                "{pair}"
+            );
+            assert_eq!(
+                johnson.path(&source, &target).unwrap().map(names),
+                expected,
+                "{pair}"
+            );
+            assert_eq!(
+                floyd_warshall.connects(&source, &target),
+                expected.is_some()
+            );
+        }
+    }
+}
+
+#[test]
+fn paths_are_accumulated() {
+    let category = tokens();
+    let all_pairs = Johnson.all_pairs(&category, 10);
+    assert_eq!(all_pairs.input_size(), &10);
+    // "a" doubles the size before "c" and "f" are applied, so they cost -40
+    // and 20
+    assert_eq!(
+        names(all_pairs.path(&0, &3).unwrap().unwrap()),
+        (vec!["a", "c", "f"], 20)
+    );
+}
+
+#[test]
+fn missing_objects_are_errors() {
+    let category = tokens();
+    let all_pairs = FloydWarshall.all_pairs(&category, 10);
+    assert!(matches!(
+        all_pairs.path(&0, &9),
+        Err(PathFindingError::MissingObject(9))
+    ));
+    assert!(matches!(
+        all_pairs.path(&9, &0),
+        Err(PathFindingError::MissingObject(9))
+    ));
+    assert!(!all_pairs.connects(&9, &0));
+}
+
+#[test]
+fn negative_cycles_are_reported_like_negatable() {
+    let cyclic = category(vec![
+        (0, 1, "a", 1, 1),
+        (1, 2, "b", 1, -3),
+        (2, 1, "c", 1, 1),
+        (2, 3, "d", 1, 1),
+    ]);
+    let Err(PathFindingError::NegativeCycle(expected)) = Negatable.shortest_path(&cyclic, 0, 3, 10)
+    else {
+        panic!("negatable should find the cycle");
+    };
+    let expected = expected
+        .iter()
+        .map(|m| m.metadata.name)
+        .collect::<HashSet<_>>();
+
+    for result in [
+        FloydWarshall.all_pairs(&cyclic, 10).path(&0, &3),
+        Johnson.all_pairs(&cyclic, 10).path(&0, &3),
+    ] {
+        let Err(PathFindingError::NegativeCycle(cycle)) = result else {
+            panic!("expected a negative cycle");
+        };
+        assert_eq!(
+            cycle
+                .iter()
+                .map(|m| m.metadata.name)
+                .collect::<HashSet<_>>(),
+            expected
+        );
+    }
+}
+
+/// 2 -> 3 -> 2 is a negative cycle that 0 and 1 cannot reach, and 4 -> 4 is a
+/// negative morphism from an object to itself.
+fn partly_cyclic() -> Category<u8, Quote> {
+    category(vec![
+        (0, 1, "a", 1, 1),
+        (1, 0, "b", 1, 2),
+        (2, 3, "c", 1, -3),
+        (3, 2, "d", 1, 1),
+        (3, 0, "e", 1, 1),
+        (5, 4, "f", 1, 1),
+        (5, 1, "g", 1, 1),
+        (4, 4, "h", 1, -1),
+    ])
+}
+
+#[test]
+fn only_pairs_that_reach_a_negative_cycle_fail() {
+    let category = partly_cyclic();
+    for all_pairs in [
+        FloydWarshall.all_pairs(&category, 10),
+        Johnson.all_pairs(&category, 10),
+    ] {
+        assert_eq!(
+            all_pairs.path(&0, &1).unwrap().map(names),
+            Some((vec!["a"], 10))
+        );
+        assert!(all_pairs.connects(&1, &0));
+        assert!(!all_pairs.connects(&3, &0));
+        for source in [2, 3] {
+            let Err(PathFindingError::NegativeCycle(cycle)) = all_pairs.path(&source, &0) else {
+                panic!("expected a negative cycle from {source}");
+            };
+            let mut cycle = cycle.iter().map(|m| m.metadata.name).collect::<Vec<_>>();
+            cycle.sort();
+            assert_eq!(cycle, vec!["c", "d"]);
+        }
+    }
+}
+
+#[test]
+fn failures_match_negatable() {
+    let category = partly_cyclic();
+    let floyd_warshall = FloydWarshall.all_pairs(&category, 10);
+    let johnson = Johnson.all_pairs(&category, 10);
+
+    for source in 0..7 {
+        for target in 0..7 {
+            let expected = Negatable
+                .shortest_path(&category, source, target, 10)
+                .map(|path| path.map(names))
+                .map_err(|_| ());
+            let pair = format!("{source} -> {target}");
+            for all_pairs in [&floyd_warshall, &johnson] {
+                let path = all_pairs
+                    .path(&source, &target)
+                    .map(|path| path.map(names))
+                    .map_err(|_| ());
+                assert_eq!(path, expected, "{pair}");
+            }
+        }
+    }
+}
+
+#[test]
+fn negative_self_loops_are_cycles() {
+    let category = partly_cyclic();
+    for all_pairs in [
+        FloydWarshall.all_pairs(&category, 10),
+        Johnson.all_pairs(&category, 10),
+    ] {
+        let Err(PathFindingError::NegativeCycle(cycle)) = all_pairs.path(&5, &0) else {
+            panic!("5 can reach the self loop");
+        };
+        assert_eq!(
+            cycle.iter().map(|m| m.metadata.name).collect::<Vec<_>>(),
+            vec!["h"]
+        );
+    }
+}
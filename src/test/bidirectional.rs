@@ -0,0 +1,222 @@
+use crate::category::Category;
+use crate::impls::SimpleMorphism;
+use crate::morphism::{
+    ApplyMorphism, InputIndependent, Morphism, MorphismMeta, MorphismOutput, TryApplyMorphism,
+};
+use crate::propagation::PassThrough;
+use crate::shortest_path::optimizer::Optimizer;
+use crate::shortest_path::path::{AppliedCompositeMorphism, WellFormedPath};
+use crate::{shortest_path::*, InfallibleResultExt};
+
+/// Multiplies the input by `rate`, and always costs `toll`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Toll {
+    name: &'static str,
+    rate: i64,
+    toll: i64,
+}
+
+impl MorphismMeta for Toll {
+    type Key = &'static str;
+
+    fn key(&self) -> &&'static str {
+        &self.name
+    }
+}
+
+impl ApplyMorphism<i64, i64, true> for Toll {
+    fn apply(&self, input: i64) -> MorphismOutput<i64, i64> {
+        MorphismOutput {
+            size: input * self.rate,
+            cost: self.toll,
+        }
+    }
+}
+
+impl InputIndependent for Toll {}
+
+/// Always costs 1, but wrongly claims to be InputIndependent, because it
+/// cannot be applied to more than `limit`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Capped {
+    name: &'static str,
+    limit: i64,
+}
+
+impl MorphismMeta for Capped {
+    type Key = &'static str;
+
+    fn key(&self) -> &&'static str {
+        &self.name
+    }
+}
+
+impl TryApplyMorphism<u8, i64, i64, true> for Capped {
+    type Error = ();
+
+    fn try_apply(
+        &self,
+        _source: &u8,
+        _target: &u8,
+        input: i64,
+    ) -> Result<MorphismOutput<i64, i64>, ()> {
+        if input > self.limit {
+            return Err(());
+        }
+        Ok(MorphismOutput {
+            size: input * 2,
+            cost: 1,
+        })
+    }
+}
+
+impl InputIndependent for Capped {}
+
+/// Several routes between 0 and 5, some of which go backwards, and 6 is not
+/// connected to anything.
+fn roads() -> Category<u8, Toll> {
+    let mut category = Category::from_morphisms(
+        [
+            (0, 1, "a", 2, 4),
+            (0, 2, "b", 1, 1),
+            (2, 1, "c", 1, 2),
+            (1, 3, "d", 3, 5),
+            (2, 3, "e", 1, 9),
+            (3, 2, "f", 1, 1),
+            (3, 4, "g", 2, 2),
+            (1, 4, "h", 1, 8),
+            (4, 5, "i", 1, 3),
+            (2, 5, "j", 5, 16),
+            (5, 0, "k", 1, 1),
+            (1, 1, "l", 1, 1),
+        ]
+        .into_iter()
+        .map(|(source, target, name, rate, toll)| {
+            Morphism::new(source, target, Toll { name, rate, toll })
+        }),
+    );
+    category.add_object(6).unwrap();
+    category
+}
+
+fn names(path: WellFormedPath<u8, Toll, u8, i64, i64>) -> (Vec<&'static str>, i64, i64) {
+    let applied: AppliedCompositeMorphism<u8, Toll, u8, i64, i64> = path.into();
+    (
+        applied
+            .morphisms
+            .iter()
+            .map(|m| m.morphism.metadata.name)
+            .collect(),
+        applied.output(),
+        applied.cost,
+    )
+}
+
+#[test]
+fn every_pair_matches_accumulating() {
+    let category = roads();
+    for source in 0..7 {
+        for target in 0..7 {
+            let expected = Accumulating
+                .shortest_path(&category, source, target, 10)
+                .safe_unwrap()
+                .map(names);
+            assert_eq!(
+                Bidirectional
+                    .shortest_path(&category, source, target, 10)
+                    .unwrap()
+                    .map(names),
+                expected,
+                "{source} -> {target}"
+            );
+        }
+    }
+}
+
+#[test]
+fn sizes_are_propagated() {
+    let category = roads();
+    let path = Bidirectional
+        .shortest_path(&category, 0, 5, 10)
+        .unwrap()
+        .unwrap();
+    assert_eq!(names(path), (vec!["b", "c", "d", "g", "i"], 60, 13));
+
+    let path = Bidirectional
+        .shortest_path_propagating(&category, 0, 5, 10, &PassThrough)
+        .unwrap()
+        .unwrap();
+    assert_eq!(names(path), (vec!["b", "c", "d", "g", "i"], 10, 13));
+}
+
+#[test]
+fn unreachable_and_missing_objects() {
+    let category = roads();
+    assert!(Bidirectional
+        .shortest_path(&category, 0, 6, 10)
+        .unwrap()
+        .is_none());
+    assert!(Bidirectional
+        .shortest_path(&category, 0, 9, 10)
+        .unwrap()
+        .is_none());
+    assert!(Bidirectional
+        .shortest_path(&category, 0, 0, 10)
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn constant_cost_counts_morphisms() {
+    let category: Category<u8, SimpleMorphism> = (0..8)
+        .map(|i| Morphism::new(i, i + 1, SimpleMorphism::new(format!("{i}"))))
+        .chain([
+            Morphism::new(0, 4, SimpleMorphism::new("0-4".to_owned())),
+            Morphism::new(4, 0, SimpleMorphism::new("4-0".to_owned())),
+            Morphism::new(5, 8, SimpleMorphism::new("5-8".to_owned())),
+        ])
+        .collect::<Vec<_>>()
+        .into();
+
+    let path = Bidirectional
+        .shortest_path(&category, 0, 8, ())
+        .unwrap()
+        .unwrap();
+    let expected = Accumulating
+        .shortest_path(&category, 0, 8, ())
+        .safe_unwrap()
+        .unwrap();
+    assert_eq!(path.cost, expected.cost);
+    assert_eq!(path.cost, 3.0.into());
+}
+
+#[test]
+fn broken_promises_are_errors() {
+    let category: Category<u8, Capped> = Category::from_morphisms([
+        Morphism::new(
+            0,
+            1,
+            Capped {
+                name: "a",
+                limit: 10,
+            },
+        ),
+        Morphism::new(
+            1,
+            2,
+            Capped {
+                name: "b",
+                limit: 10,
+            },
+        ),
+    ]);
+    // "b" can be applied to 10, which is used during the search, but not to
+    // the 20 that "a" outputs
+    let Err(PathFindingError::ApplicationFailed(error)) =
+        Bidirectional.shortest_path(&category, 0, 2, 10)
+    else {
+        panic!("expected an application error");
+    };
+    assert_eq!(error.step, 1);
+    assert_eq!(error.morphism.metadata.name, "b");
+}
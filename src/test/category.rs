@@ -0,0 +1,197 @@
+use std::collections::HashSet;
+
+use crate::category::{Category, CategoryError, RemovalPolicy};
+use crate::impls::{DeductiveLinearCost, Float, SimpleMorphism};
+use crate::morphism::{ApplyMorphism, HasLogic, Morphism};
+
+type MyMorph = Morphism<u8, SimpleMorphism>;
+
+fn morphism(source: u8, target: u8, name: &str) -> MyMorph {
+    MyMorph::new(source, target, SimpleMorphism::new(name.to_owned()))
+}
+
+fn get_category() -> Category<u8, SimpleMorphism> {
+    vec![
+        morphism(0, 1, "a"),
+        morphism(0, 1, "b"),
+        morphism(1, 2, "c"),
+        morphism(2, 0, "d"),
+    ]
+    .into()
+}
+
+fn outbound(category: &Category<u8, SimpleMorphism>, id: u8) -> Vec<MyMorph> {
+    category.get_outbound(&id).unwrap().cloned().collect()
+}
+
+#[test]
+fn remove_morphism() {
+    let mut category = get_category();
+    category
+        .remove_morphism(&morphism(0, 1, "a").key())
+        .unwrap();
+
+    assert_eq!(3, category.morphisms().count());
+    assert_eq!(vec![morphism(0, 1, "b")], outbound(&category, 0));
+    assert!(matches!(
+        category.remove_morphism(&morphism(0, 1, "a").key()),
+        Err(CategoryError::MissingMorphism(..))
+    ));
+}
+
+#[test]
+fn remove_object_refuse() {
+    let mut category = get_category();
+    assert!(matches!(
+        category.remove_object(&1, RemovalPolicy::Refuse),
+        Err(CategoryError::ObjectHasMorphisms(_, 3))
+    ));
+    assert_eq!(4, category.morphisms().count());
+    assert!(category.get_object(&1).is_some());
+}
+
+#[test]
+fn remove_object_cascade() {
+    let mut category = get_category();
+    let (object, removed) = category.remove_object(&1, RemovalPolicy::Cascade).unwrap();
+
+    assert_eq!(1, object);
+    assert_eq!(3, removed.len());
+    assert!(category.get_object(&1).is_none());
+    assert!(category.get_outbound(&1).is_none());
+    assert!(outbound(&category, 0).is_empty());
+    assert_eq!(
+        vec![&morphism(2, 0, "d")],
+        category.morphisms().collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn retain_morphisms() {
+    let mut category = get_category();
+    category.retain_morphisms(|m| m.metadata.meta != "b" && m.metadata.meta != "d");
+
+    assert_eq!(2, category.morphisms().count());
+    assert_eq!(vec![morphism(0, 1, "a")], outbound(&category, 0));
+    assert!(outbound(&category, 2).is_empty());
+}
+
+#[test]
+fn update_morphism() {
+    type Pool = Morphism<u8, SimpleMorphism<String, DeductiveLinearCost>>;
+    let pool = |rate: i32| {
+        Pool::new(
+            0,
+            1,
+            SimpleMorphism {
+                meta: "pool".to_owned(),
+                logic: DeductiveLinearCost {
+                    rate: rate.into(),
+                    constant: 0.into(),
+                },
+            },
+        )
+    };
+    let mut category = Category::from(vec![pool(1)]);
+    let key = pool(1).key();
+    category
+        .update_morphism(&key, |logic| logic.rate = 2.into())
+        .unwrap();
+
+    let updated = category.get_morphism(&key).unwrap();
+    assert_eq!(pool(2).metadata, updated.metadata);
+    assert_eq!(
+        vec![pool(2).metadata],
+        category
+            .get_outbound(&0)
+            .unwrap()
+            .map(|m| m.metadata.clone())
+            .collect::<Vec<_>>()
+    );
+    let cost = ApplyMorphism::<Float, Float, true>::apply(&updated.metadata, 10.into()).cost;
+    assert_eq!(Float::from(20), cost);
+}
+
+/// Metadata that is its own key, so updating it always changes the key.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Named(String);
+
+crate::self_keyed!(Named);
+
+impl HasLogic for Named {
+    type Logic = String;
+
+    fn logic_mut(&mut self) -> &mut String {
+        &mut self.0
+    }
+}
+
+#[test]
+fn update_morphism_cannot_change_key() {
+    let named = Morphism::new(0, 1, Named("a".to_owned()));
+    let mut category: Category<u8, Named> = vec![named.clone()].into();
+    assert!(matches!(
+        category.update_morphism(&named.key(), |name| name.push('b')),
+        Err(CategoryError::KeyChanged(..))
+    ));
+    assert_eq!(
+        &Named("a".to_owned()),
+        &category.get_morphism(&named.key()).unwrap().metadata
+    );
+    category.update_morphism(&named.key(), |_| {}).unwrap();
+}
+
+#[test]
+fn morphisms_are_identified_by_key() {
+    type Pool = Morphism<u8, SimpleMorphism<String, DeductiveLinearCost>>;
+    let pool = |rate: i32| {
+        Pool::new(
+            0,
+            1,
+            SimpleMorphism {
+                meta: "pool".to_owned(),
+                logic: DeductiveLinearCost {
+                    rate: rate.into(),
+                    constant: 0.into(),
+                },
+            },
+        )
+    };
+    assert_eq!(pool(1), pool(2));
+    assert_eq!(1, HashSet::from([pool(1), pool(2)]).len());
+    assert_ne!(pool(1), Pool::new(0, 2, pool(1).metadata));
+}
+
+#[test]
+fn inbound_and_hom() {
+    let mut category = get_category();
+    let mut inbound = category
+        .get_inbound(&1)
+        .unwrap()
+        .cloned()
+        .collect::<Vec<_>>();
+    inbound.sort_by(|a, b| a.metadata.meta.cmp(&b.metadata.meta));
+    assert_eq!(vec![morphism(0, 1, "a"), morphism(0, 1, "b")], inbound);
+    assert_eq!(2, category.hom(&0, &1).count());
+    assert_eq!(0, category.hom(&1, &0).count());
+
+    category
+        .remove_morphism(&morphism(0, 1, "a").key())
+        .unwrap();
+    assert_eq!(
+        vec![&morphism(0, 1, "b")],
+        category.hom(&0, &1).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec![morphism(0, 1, "b")],
+        category
+            .get_inbound(&1)
+            .unwrap()
+            .cloned()
+            .collect::<Vec<_>>()
+    );
+
+    category.remove_object(&1, RemovalPolicy::Cascade).unwrap();
+    assert!(category.get_inbound(&1).is_none());
+    assert!(category.get_inbound(&2).unwrap().next().is_none());
+}
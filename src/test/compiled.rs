@@ -0,0 +1,87 @@
+use crate::category::Category;
+use crate::shortest_path::optimizer::Optimizer;
+use crate::shortest_path::*;
+
+use super::{quote_names as names, quotes as category, Quote, APPLIED};
+
+fn tokens() -> Category<u8, Quote> {
+    category(vec![
+        (0, 1, "a", 2, 1),
+        (0, 2, "b", 1, 3),
+        (1, 2, "c", 1, 1),
+        (1, 3, "d", 3, 3),
+        (2, 3, "e", 1, -1),
+        (3, 4, "f", 1, 2),
+        (2, 4, "g", 1, 4),
+        (4, 0, "h", 1, 1),
+    ])
+}
+
+#[test]
+fn queries_match_negatable() {
+    let category = tokens();
+    let compiled = CompiledCategory::new(&category, 10);
+
+    for source in 0..5 {
+        let tree = compiled.shortest_path_tree(source).unwrap().unwrap();
+        let targets = (0..5).collect::<Vec<_>>();
+        let paths = compiled.shortest_paths(source, &targets).unwrap();
+        let expected = Negatable
+            .shortest_paths(&category, vec![(source, 10)], targets.clone())
+            .unwrap();
+        assert_eq!(
+            paths.iter().cloned().map(names).collect::<Vec<_>>(),
+            expected.into_iter().map(names).collect::<Vec<_>>()
+        );
+        for target in targets {
+            let expected = Negatable
+                .shortest_path(&category, source, target, 10)
+                .unwrap()
+                .map(names);
+            assert_eq!(
+                compiled.shortest_path(source, target).unwrap().map(names),
+                expected
+            );
+            assert_eq!(tree.path_to(&target).map(names), expected);
+        }
+    }
+}
+
+#[test]
+fn morphisms_are_applied_once_when_compiled() {
+    let category = tokens();
+    let before = APPLIED.get();
+    let compiled = CompiledCategory::new(&category, 10);
+    assert_eq!(APPLIED.get() - before, 8);
+    assert_eq!(compiled.input_size(), &10);
+
+    // each query only reapplies the morphisms in its path
+    let before = APPLIED.get();
+    let path = compiled.shortest_path(0, 4).unwrap().unwrap();
+    assert_eq!(names(path), (vec!["a", "c", "e", "f"], 50));
+    assert_eq!(APPLIED.get() - before, 4);
+}
+
+#[test]
+fn errors_are_the_same_as_negatable() {
+    let tokens = tokens();
+    let compiled = CompiledCategory::new(&tokens, 10);
+    assert!(compiled.shortest_path(9, 4).unwrap().is_none());
+    assert!(matches!(
+        compiled.shortest_path(0, 9),
+        Err(PathFindingError::MissingObject(9))
+    ));
+    assert!(compiled.shortest_path_tree(9).unwrap().is_none());
+    assert!(compiled.shortest_paths(9, &[4]).unwrap().is_empty());
+
+    let cyclic: Category<u8, Quote> = category(vec![
+        (0, 1, "a", 1, 1),
+        (1, 2, "b", 1, -3),
+        (2, 1, "c", 1, 1),
+    ]);
+    let compiled = CompiledCategory::new(&cyclic, 10);
+    assert!(matches!(
+        compiled.shortest_path(0, 2),
+        Err(PathFindingError::NegativeCycle(_))
+    ));
+}
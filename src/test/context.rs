@@ -0,0 +1,114 @@
+use crate::category::{Category, HasId};
+use crate::impls::Float;
+use crate::morphism::{ApplyMorphismWithContext, Morphism, MorphismMeta, MorphismOutput};
+use crate::shortest_path::optimizer::Optimizer;
+use crate::shortest_path::path::AppliedCompositeMorphism;
+use crate::{shortest_path::*, InfallibleResultExt};
+
+/// Sizes are in the smallest unit of each token.
+#[derive(Clone, Debug)]
+struct Token {
+    id: u8,
+    decimals: i32,
+}
+
+impl HasId<u8> for Token {
+    fn id(&self) -> u8 {
+        self.id
+    }
+}
+
+/// Converts between the units of two tokens, with a fee of `rate` for every
+/// whole source token.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Bridge {
+    name: &'static str,
+    rate: Float,
+}
+
+impl MorphismMeta for Bridge {
+    type Key = &'static str;
+
+    fn key(&self) -> &&'static str {
+        &self.name
+    }
+}
+
+impl ApplyMorphismWithContext<Token, Float, Float, true> for Bridge {
+    fn apply_with_context(&self, source: &Token, target: &Token, input: Float) -> MorphismOutput {
+        let whole_tokens = input / 10f64.powi(source.decimals);
+        MorphismOutput {
+            size: whole_tokens * 10f64.powi(target.decimals),
+            cost: whole_tokens * self.rate,
+        }
+    }
+}
+
+/// The indirect route is cheaper, but only with accumulation. Without it, the
+/// second bridge would be applied to a million units of a token with only two
+/// decimals.
+fn category() -> Category<u8, Bridge, Token> {
+    let mut category = Category::new();
+    category
+        .add_objects(vec![
+            Token { id: 0, decimals: 6 },
+            Token {
+                id: 1,
+                decimals: 18,
+            },
+            Token { id: 2, decimals: 2 },
+        ])
+        .unwrap();
+    let bridge = |source, target, name, rate: i32| {
+        Morphism::new(
+            source,
+            target,
+            Bridge {
+                name,
+                rate: rate.into(),
+            },
+        )
+    };
+    category
+        .add_morphisms(vec![
+            bridge(0, 1, "direct", 3),
+            bridge(0, 2, "first", 1),
+            bridge(2, 1, "second", 1),
+        ])
+        .unwrap();
+    category
+}
+
+#[test]
+fn optimizers_apply_with_objects() {
+    let one_token = Float::from(1e6);
+    let accumulating = Accumulating
+        .shortest_path(&category(), 0, 1, one_token)
+        .safe_unwrap()
+        .unwrap();
+    let negatable = Negatable
+        .shortest_path(&category(), 0, 1, one_token)
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(Float::from(2), accumulating.cost);
+    assert_eq!(5, accumulating.vertices.len());
+    assert_eq!(Float::from(3), negatable.cost);
+    assert_eq!(3, negatable.vertices.len());
+}
+
+#[test]
+fn reapply_with_objects() {
+    let path = Accumulating
+        .shortest_path(&category(), 0, 1, 1e6.into())
+        .safe_unwrap()
+        .unwrap();
+    let applied: AppliedCompositeMorphism<u8, Bridge, Token> = path.into();
+    assert_eq!(Float::from(1e18), applied.output());
+
+    let reapplied = applied.reapply::<true>(5e6.into());
+    assert_eq!(Float::from(10), reapplied.cost);
+    assert_eq!(Float::from(5e18), reapplied.output());
+    let costs: Vec<_> = reapplied.morphisms.iter().map(|m| m.cost).collect();
+    assert_eq!(vec![Float::from(5), Float::from(5)], costs);
+}
@@ -0,0 +1,66 @@
+use crate::cost::CostMeasure;
+use crate::morphism::ApplyMorphism;
+use crate::shortest_path::optimizer::Optimizer;
+use crate::shortest_path::path::AppliedCompositeMorphism;
+use crate::shortest_path::query::Query;
+use crate::{shortest_path::*, InfallibleResultExt};
+
+use super::{quote_names as names, quotes};
+
+#[test]
+fn integer_costs_saturate() {
+    assert_eq!(i8::MAX, 100i8.plus(100));
+    assert_eq!(i8::MIN, (-100i8).plus(-100));
+    assert_eq!(u64::MAX, u64::sum([u64::MAX - 1, 2, 3]));
+    assert_eq!(i64::MAX - 1, i64::MAX.plus(-1));
+    assert_eq!(i8::MAX, 100i8.minus(-100));
+    assert_eq!(0, 1u8.minus(2));
+}
+
+/// The two steps of the indirect route add up to more than i64::MAX.
+#[test]
+fn paths_near_max_cost_do_not_overflow() {
+    let category = quotes::<true>(vec![
+        (0, 1, "first", 1, i64::MAX - 10),
+        (1, 2, "second", 1, 100),
+        (0, 2, "direct", 1, i64::MAX - 5),
+    ]);
+    let expected = Some((vec!["direct"], i64::MAX - 5));
+
+    let accumulating = Accumulating.shortest_path(&category, 0, 2, 1).safe_unwrap();
+    assert_eq!(expected, accumulating.map(names));
+    let negatable = Negatable.shortest_path(&category, 0, 2, 1).unwrap();
+    assert_eq!(expected, negatable.map(names));
+    let label_correcting = LabelCorrecting {
+        max_hops: 4,
+        max_states: 10,
+    }
+    .shortest_path(&category, 0, 2, 1)
+    .unwrap();
+    assert_eq!(expected, label_correcting.map(names));
+    for all_pairs in [
+        FloydWarshall.all_pairs(&category, 1),
+        Johnson.all_pairs(&category, 1),
+    ] {
+        assert_eq!(expected, all_pairs.path(&0, &2).unwrap().map(names));
+    }
+}
+
+/// The legs of the query add up to more than i64::MAX when they are joined
+/// and when the path is reapplied.
+#[test]
+fn joined_and_reapplied_paths_do_not_overflow() {
+    let category = quotes::<true>(vec![
+        (0, 1, "first", 1, i64::MAX - 10),
+        (1, 2, "second", 1, 100),
+    ]);
+    let joined = Accumulating
+        .shortest_path_query(&category, &Query::new(0, 2, 1).via(1))
+        .safe_unwrap()
+        .unwrap();
+    assert_eq!((vec!["first", "second"], i64::MAX), names(joined.clone()));
+
+    let applied: AppliedCompositeMorphism<_, _, _, i64, i64> = joined.into();
+    assert_eq!(i64::MAX, applied.apply(1).cost);
+    assert_eq!(i64::MAX, applied.reapply(1).cost);
+}
@@ -6,6 +6,8 @@ use crate::morphism::{ApplyMorphism, Morphism, MorphismOutput};
 use crate::shortest_path::optimizer::Optimizer;
 use crate::{shortest_path::*, InfallibleResultExt};
 
+use super::{quote_names, quotes};
+
 type MyMorph = Morphism<MyObjId, MyMorphMeta>;
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -14,6 +16,8 @@ enum MyMorphMeta {
     Dynamic(Float, Float),
 }
 
+crate::self_keyed!(MyMorphMeta);
+
 impl ApplyMorphism<Float, Float, true> for MyMorphMeta {
     fn apply(&self, input: Float) -> MorphismOutput {
         match self.clone() {
@@ -121,6 +125,53 @@ fn dijkstra_pathfinding() {
     assert_eq!(path.cost, 250.into());
 }
 
+/// same as dijkstra, but the heuristic knows that the last step costs at least
+/// 70 with an input of 10 or more.
+#[test]
+fn astar_pathfinding() {
+    let heuristic = |id: &MyObjId, size: &Float| match id.0 {
+        [1, 1] => 0.into(),
+        [1, 0] => Float::from(70).min(*size * 7.0),
+        _ => Float::from(70),
+    };
+    let path = AStar(heuristic)
+        .shortest_path(&get_category(), MyObjId([0, 0]), MyObjId([1, 1]), 10.into())
+        .safe_unwrap()
+        .unwrap();
+
+    assert_eq!(path.cost, 250.into());
+}
+
+/// the heuristic is admissible and consistent, but it underestimates the
+/// last step much more for large sizes, so the expensive state of 1 is
+/// expanded before the cheap route through 4 reaches 1 with a smaller size.
+#[test]
+fn astar_does_not_block_cheaper_states() {
+    let category = quotes::<true>(vec![
+        (0, 2, "b", 3, 1),
+        (2, 1, "c", 1, 1),
+        (0, 4, "a", 1, 2),
+        (4, 1, "e", 1, 1),
+        (1, 3, "d", 1, 10),
+    ]);
+    let heuristic = |id: &u8, size: &i64| match id {
+        1 => (10 - 3 * size).max(0),
+        4 => (9 - size).max(0),
+        _ => 0,
+    };
+
+    let expected = Accumulating
+        .shortest_path(&category, 0, 3, 1)
+        .safe_unwrap()
+        .unwrap();
+    assert_eq!(quote_names(expected), (vec!["a", "e", "d"], 13));
+    let path = AStar(heuristic)
+        .shortest_path(&category, 0, 3, 1)
+        .safe_unwrap()
+        .unwrap();
+    assert_eq!(quote_names(path), (vec!["a", "e", "d"], 13));
+}
+
 /// prefers the step2_dynamic because sizes are not accumulated during
 /// optimization, and 70 < 100. the return value does include accumulation, so
 /// the cost accurately reflects the large value of the sub-optimal path that
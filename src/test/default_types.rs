@@ -1,6 +1,7 @@
 use crate::impls::{DeductiveLinearCost, Float, SimpleMorphism};
-use crate::morphism::Morphism;
+use crate::morphism::{ApplyMorphism, CompositeMorphism, Morphism};
 use crate::shortest_path::optimizer::Optimizer;
+use crate::shortest_path::path::AppliedCompositeMorphism;
 use crate::vertex::Vertex;
 use crate::{shortest_path::*, InfallibleResultExt};
 
@@ -62,7 +63,22 @@ fn dijkstra_pathfinding() {
         .shortest_path(&transitions().into(), 2, 0, 100.into())
         .safe_unwrap()
         .unwrap();
-    let expected = expected([100, 0, 0]);
+    let expected = expected([100, 0, 0], [1010, 1]);
+    assert_eq!(expected.len(), path.vertices.len());
+    for (expected_vertex, actual_vertex) in expected.into_iter().zip(path.vertices.iter()) {
+        assert_eq!(&expected_vertex, actual_vertex);
+    }
+    assert_eq!(Float::from(1011), path.cost);
+}
+
+#[test]
+fn astar_pathfinding() {
+    let heuristic = |id: &u8, _: &Float| if *id == 0 { 0.into() } else { 1.into() };
+    let path = AStar(heuristic)
+        .shortest_path(&transitions().into(), 2, 0, 100.into())
+        .safe_unwrap()
+        .unwrap();
+    let expected = expected([100, 0, 0], [1010, 1]);
     assert_eq!(expected.len(), path.vertices.len());
     for (expected_vertex, actual_vertex) in expected.into_iter().zip(path.vertices.iter()) {
         assert_eq!(&expected_vertex, actual_vertex);
@@ -76,7 +92,7 @@ fn bellman_ford_petgraph() {
         .shortest_path(&transitions().into(), 2, 0, 100.into())
         .unwrap()
         .unwrap();
-    let expected = expected([100, 0, 0]);
+    let expected = expected([100, 0, 0], [1010, 1]);
     assert_eq!(expected.len(), path.vertices.len());
     for (expected_vertex, actual_vertex) in expected.into_iter().zip(path.vertices.iter()) {
         assert_eq!(&expected_vertex, actual_vertex);
@@ -84,7 +100,7 @@ fn bellman_ford_petgraph() {
     assert_eq!(Float::from(1011), path.cost);
 }
 
-fn expected(sizes: [i32; 3]) -> Vec<Vertex<u8, MyMorphMeta>> {
+fn expected(sizes: [i32; 3], costs: [i32; 2]) -> Vec<Vertex<u8, MyMorphMeta>> {
     let transitions = transitions();
     vec![
         Vertex::Object {
@@ -94,6 +110,7 @@ fn expected(sizes: [i32; 3]) -> Vec<Vertex<u8, MyMorphMeta>> {
         Vertex::Morphism {
             inner: transitions[3].clone(),
             input: sizes[0].into(),
+            cost: costs[0].into(),
         },
         Vertex::Object {
             inner: 1.into(),
@@ -102,6 +119,7 @@ fn expected(sizes: [i32; 3]) -> Vec<Vertex<u8, MyMorphMeta>> {
         Vertex::Morphism {
             inner: transitions[0].clone(),
             input: sizes[1].into(),
+            cost: costs[1].into(),
         },
         Vertex::Object {
             inner: 0.into(),
@@ -109,3 +127,52 @@ fn expected(sizes: [i32; 3]) -> Vec<Vertex<u8, MyMorphMeta>> {
         },
     ]
 }
+
+fn fractional_chain() -> Vec<MyMorph> {
+    let step = |source: u8, rate: f64, constant: f64| {
+        MyMorph::new(
+            source,
+            source + 1,
+            MyMorphMeta {
+                meta: format!("{source}to{}", source + 1),
+                logic: DeductiveLinearCost {
+                    rate: rate.into(),
+                    constant: constant.into(),
+                },
+            },
+        )
+    };
+    vec![step(0, 0.1, 1.0), step(1, 0.2, 2.0), step(2, 0.05, 3.0)]
+}
+
+#[test]
+fn composite_cost_is_sum_of_steps() {
+    let category = fractional_chain().into();
+    let path = Accumulating
+        .shortest_path(&category, 0, 3, 100.into())
+        .safe_unwrap()
+        .unwrap();
+    let step_costs = path.vertices.iter().filter_map(Vertex::cost).copied();
+    assert_eq!(path.cost, step_costs.fold(Float::from(0), |a, b| a + b));
+
+    let applied: AppliedCompositeMorphism<u8, MyMorphMeta> = path.clone().into();
+    let morphisms = applied.morphisms.iter().map(|m| m.morphism.clone());
+    let composite = CompositeMorphism(morphisms.collect::<Vec<_>>().try_into().unwrap());
+    let apply = |m: &dyn ApplyMorphism<Float, Float, true>, input: i32| m.apply(input.into());
+    assert_eq!(path.cost, apply(&applied, 100).cost);
+    assert_eq!(path.cost, apply(&composite, 100).cost);
+    assert_eq!(path.cost, applied.clone().reapply::<true>(100.into()).cost);
+
+    let larger = Accumulating
+        .shortest_path(&category, 0, 3, 200.into())
+        .safe_unwrap()
+        .unwrap();
+    let reapplied = applied.reapply::<true>(200.into());
+    assert_eq!(larger.cost, reapplied.cost);
+    assert_eq!(reapplied.output(), apply(&composite, 200).size);
+    assert_eq!(reapplied.cost, apply(&composite, 200).cost);
+    let larger_costs = larger.vertices.iter().filter_map(Vertex::cost);
+    for (applied, cost) in reapplied.morphisms.iter().zip(larger_costs) {
+        assert_eq!(&applied.cost, cost);
+    }
+}
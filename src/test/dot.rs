@@ -0,0 +1,117 @@
+use crate::category::Category;
+use crate::impls::{DeductiveLinearCost, Float, SimpleMorphism};
+use crate::morphism::Morphism;
+use crate::shortest_path::optimizer::Optimizer;
+use crate::shortest_path::path::{Path, WellFormedPath};
+use crate::vertex::Vertex;
+use crate::{shortest_path::*, InfallibleResultExt};
+
+type MyMorphMeta = SimpleMorphism<String, DeductiveLinearCost>;
+
+fn morphism(source: u8, target: u8, name: &str) -> Morphism<u8, MyMorphMeta> {
+    Morphism::new(
+        source,
+        target,
+        MyMorphMeta {
+            meta: name.to_owned(),
+            logic: DeductiveLinearCost {
+                rate: 0.into(),
+                constant: 1.into(),
+            },
+        },
+    )
+}
+
+fn get_category() -> Category<u8, MyMorphMeta> {
+    vec![
+        morphism(0, 1, "a"),
+        morphism(1, 2, "b"),
+        morphism(0, 2, "c\"quoted\""),
+    ]
+    .into()
+}
+
+#[test]
+fn to_dot() {
+    let dot = get_category().to_dot();
+
+    assert!(dot.starts_with("digraph {\n"));
+    assert!(dot.ends_with("}\n"));
+    for line in [
+        r#"    "0" [label="0"];"#,
+        r#"    "2" [label="2"];"#,
+        r#"    "0" -> "1" [label="a"];"#,
+        r#"    "1" -> "2" [label="b"];"#,
+        r#"    "0" -> "2" [label="c\"quoted\""];"#,
+    ] {
+        assert!(dot.contains(line), "missing {line} in {dot}");
+    }
+}
+
+#[test]
+fn to_dot_with_path() {
+    let category = get_category();
+    let mut category_with_detour = category.clone();
+    category_with_detour
+        .remove_morphism(&morphism(0, 2, "c\"quoted\"").key())
+        .unwrap();
+    let path = Accumulating
+        .shortest_path(&category_with_detour, 0, 2, 10.into())
+        .safe_unwrap()
+        .unwrap();
+    let dot = category.to_dot_with_path(path);
+
+    for line in [
+        r#"    "1" [label="1", color=red, penwidth=2];"#,
+        r#"    "0" -> "2" [label="c\"quoted\""];"#,
+        r#"    "0" -> "1" [label="1: a\n10 ─▶ 9\ncost: 1", color=red, fontcolor=red, penwidth=2];"#,
+        r#"    "1" -> "2" [label="2: b\n9 ─▶ 8\ncost: 1", color=red, fontcolor=red, penwidth=2];"#,
+    ] {
+        assert!(dot.contains(line), "missing {line} in {dot}");
+    }
+    assert!(!dot.contains(r#"    "0" -> "1" [label="a"];"#));
+}
+
+#[test]
+fn repeated_morphisms_are_merged_into_one_edge() {
+    let forth = morphism(0, 1, "forth");
+    let back = morphism(1, 0, "back");
+    let category: Category<u8, MyMorphMeta> = vec![forth.clone(), back.clone()].into();
+    let object = |inner, size: i32| Vertex::<_, _, _, Float, Float>::Object {
+        inner,
+        size: size.into(),
+    };
+    let step = |inner: &Morphism<u8, MyMorphMeta>, input: i32| Vertex::Morphism {
+        inner: inner.clone(),
+        input: Float::from(input),
+        cost: Float::from(1),
+    };
+    let path = WellFormedPath::try_from(Path {
+        vertices: vec![
+            object(0, 10),
+            step(&forth, 10),
+            object(1, 9),
+            step(&back, 9),
+            object(0, 8),
+            step(&forth, 8),
+            object(1, 7),
+        ]
+        .try_into()
+        .unwrap(),
+        cost: 3.into(),
+    })
+    .unwrap();
+    let dot = category.to_dot_with_path(path);
+
+    let edges = dot
+        .lines()
+        .filter(|line| line.contains("->"))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        edges,
+        [
+            r#"    "0" -> "1" [label="1: forth\n10 ─▶ 9\ncost: 1\n3: forth\n8 ─▶ 7\ncost: 1", color=red, fontcolor=red, penwidth=2];"#,
+            r#"    "1" -> "0" [label="2: back\n9 ─▶ 8\ncost: 1", color=red, fontcolor=red, penwidth=2];"#,
+        ]
+    );
+}
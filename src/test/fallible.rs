@@ -0,0 +1,193 @@
+use crate::category::Category;
+use crate::impls::Float;
+use crate::morphism::{Morphism, MorphismMeta, MorphismOutput, TryApplyMorphism};
+use crate::shortest_path::optimizer::Optimizer;
+use crate::shortest_path::path::{ApplicationError, AppliedCompositeMorphism, WellFormedPath};
+use crate::{shortest_path::*, InfallibleResultExt};
+
+#[derive(Debug, PartialEq)]
+struct InsufficientLiquidity;
+
+/// Multiplies the input by `rate` for a flat `fee`, but only if the input is
+/// no more than `liquidity`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Pool {
+    name: &'static str,
+    fee: Float,
+    rate: Float,
+    liquidity: Float,
+}
+
+impl MorphismMeta for Pool {
+    type Key = &'static str;
+
+    fn key(&self) -> &&'static str {
+        &self.name
+    }
+}
+
+impl TryApplyMorphism<u8, Float, Float, true> for Pool {
+    type Error = InsufficientLiquidity;
+
+    fn try_apply(
+        &self,
+        _source: &u8,
+        _target: &u8,
+        input: Float,
+    ) -> Result<MorphismOutput, InsufficientLiquidity> {
+        if input > self.liquidity {
+            return Err(InsufficientLiquidity);
+        }
+        Ok(MorphismOutput {
+            size: input * self.rate,
+            cost: self.fee,
+        })
+    }
+}
+
+fn category(pools: Vec<(u8, u8, &'static str, i32, i32, i32)>) -> Category<u8, Pool> {
+    let mut category = Category::new();
+    category.add_objects(vec![0, 1, 2]).unwrap();
+    category
+        .add_morphisms(
+            pools
+                .into_iter()
+                .map(|(source, target, name, fee, rate, liquidity)| {
+                    Morphism::new(
+                        source,
+                        target,
+                        Pool {
+                            name,
+                            fee: fee.into(),
+                            rate: rate.into(),
+                            liquidity: liquidity.into(),
+                        },
+                    )
+                })
+                .collect::<Vec<_>>(),
+        )
+        .unwrap();
+    category
+}
+
+/// The shallow pool is cheaper, but it can only be used for small inputs.
+fn parallel() -> Category<u8, Pool> {
+    category(vec![
+        (0, 1, "shallow", 1, 1, 50),
+        (0, 1, "deep", 5, 1, 1000),
+    ])
+}
+
+/// Both pools can be applied to 100, but the second cannot be applied to the
+/// output of the first.
+fn chain() -> Category<u8, Pool> {
+    category(vec![
+        (0, 1, "double", 1, 2, 1000),
+        (1, 2, "limited", 1, 1, 150),
+    ])
+}
+
+fn names(path: &WellFormedPath<u8, Pool>) -> Vec<&'static str> {
+    let applied: AppliedCompositeMorphism<u8, Pool> = path.clone().into();
+    applied
+        .morphisms
+        .iter()
+        .map(|m| m.morphism.metadata.name)
+        .collect()
+}
+
+#[test]
+fn failed_morphisms_are_pruned() {
+    let small = Accumulating
+        .shortest_path(&parallel(), 0, 1, 10.into())
+        .safe_unwrap()
+        .unwrap();
+    assert_eq!(vec!["shallow"], names(&small));
+    assert_eq!(Float::from(1), small.cost);
+
+    let large = Accumulating
+        .shortest_path(&parallel(), 0, 1, 100.into())
+        .safe_unwrap()
+        .unwrap();
+    assert_eq!(vec!["deep"], names(&large));
+    assert_eq!(Float::from(5), large.cost);
+
+    let negatable = Negatable
+        .shortest_path(&parallel(), 0, 1, 100.into())
+        .unwrap()
+        .unwrap();
+    assert_eq!(vec!["deep"], names(&negatable));
+
+    let label_correcting = LabelCorrecting {
+        max_hops: 10,
+        max_states: 100,
+    }
+    .shortest_path(&parallel(), 0, 1, 100.into())
+    .unwrap()
+    .unwrap();
+    assert_eq!(vec!["deep"], names(&label_correcting));
+
+    assert!(Accumulating
+        .shortest_path(&chain(), 0, 2, 100.into())
+        .safe_unwrap()
+        .is_none());
+}
+
+#[test]
+fn reapply_reports_failed_step() {
+    let Err(PathFindingError::ApplicationFailed(error)) =
+        Negatable.shortest_path(&chain(), 0, 2, 100.into())
+    else {
+        panic!("the second pool should fail after accumulation");
+    };
+    assert_eq!(1, error.step);
+    assert_eq!("limited", error.morphism.metadata.name);
+    assert_eq!(InsufficientLiquidity, error.error);
+
+    let path = Accumulating
+        .shortest_path(&chain(), 0, 2, 50.into())
+        .safe_unwrap()
+        .unwrap();
+    let applied: AppliedCompositeMorphism<u8, Pool> = path.into();
+    assert_eq!(Float::from(100), applied.output());
+    let Err(ApplicationError { step, error, .. }) = applied.clone().try_reapply(100.into()) else {
+        panic!("the second pool should fail");
+    };
+    assert_eq!((1, InsufficientLiquidity), (step, error));
+    let reapplied = applied.try_reapply(75.into()).unwrap();
+    assert_eq!(Float::from(150), reapplied.output());
+}
+
+#[test]
+fn label_correcting_prunes_failures_like_accumulating() {
+    let label_correcting = LabelCorrecting {
+        max_hops: 10,
+        max_states: 100,
+    };
+    // the second pool fails after accumulation, so there is no route
+    assert!(label_correcting
+        .shortest_path(&chain(), 0, 2, 100.into())
+        .unwrap()
+        .is_none());
+
+    // a failure in a branch that does not lead to the target is ignored
+    let mut branching = chain();
+    branching.add_object(3).unwrap();
+    branching
+        .add_morphism(Morphism::new(
+            0,
+            3,
+            Pool {
+                name: "dead end",
+                fee: 1.into(),
+                rate: 1.into(),
+                liquidity: 10.into(),
+            },
+        ))
+        .unwrap();
+    let path = label_correcting
+        .shortest_path(&branching, 0, 2, 50.into())
+        .unwrap()
+        .unwrap();
+    assert_eq!(vec!["double", "limited"], names(&path));
+}
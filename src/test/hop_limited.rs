@@ -0,0 +1,165 @@
+use crate::category::Category;
+use crate::morphism::{ApplyMorphism, Morphism, MorphismMeta, MorphismOutput};
+use crate::shortest_path::optimizer::Optimizer;
+use crate::shortest_path::path::{AppliedCompositeMorphism, WellFormedPath};
+use crate::{shortest_path::*, InfallibleResultExt};
+
+/// A morphism with a constant cost. NON_NEGATIVE is part of the type so that
+/// each category works with the intended optimizers.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Hop<const NON_NEGATIVE: bool> {
+    name: &'static str,
+    cost: i64,
+}
+
+impl<const NON_NEGATIVE: bool> MorphismMeta for Hop<NON_NEGATIVE> {
+    type Key = &'static str;
+
+    fn key(&self) -> &&'static str {
+        &self.name
+    }
+}
+
+impl<const NON_NEGATIVE: bool> ApplyMorphism<(), i64, NON_NEGATIVE> for Hop<NON_NEGATIVE> {
+    fn apply(&self, _input: ()) -> MorphismOutput<(), i64> {
+        MorphismOutput {
+            size: (),
+            cost: self.cost,
+        }
+    }
+}
+
+fn category<const NON_NEGATIVE: bool>(
+    hops: Vec<(u8, u8, &'static str, i64)>,
+) -> Category<u8, Hop<NON_NEGATIVE>> {
+    Category::from_morphisms(
+        hops.into_iter()
+            .map(|(source, target, name, cost)| Morphism::new(source, target, Hop { name, cost })),
+    )
+}
+
+/// The more morphisms a path has, the cheaper it is.
+fn staircase<const NON_NEGATIVE: bool>() -> Category<u8, Hop<NON_NEGATIVE>> {
+    category(vec![
+        (0, 3, "direct", 10),
+        (0, 1, "a", 1),
+        (1, 3, "shortcut", 5),
+        (1, 2, "b", 1),
+        (2, 3, "c", 1),
+    ])
+}
+
+fn names<const NON_NEGATIVE: bool>(
+    path: WellFormedPath<u8, Hop<NON_NEGATIVE>, u8, (), i64>,
+) -> Vec<&'static str> {
+    let applied: AppliedCompositeMorphism<u8, Hop<NON_NEGATIVE>, u8, (), i64> = path.into();
+    applied
+        .morphisms
+        .iter()
+        .map(|m| m.morphism.metadata.name)
+        .collect()
+}
+
+#[test]
+fn every_optimizer_honors_the_limit() {
+    let expected = [
+        (1, vec!["direct"]),
+        (2, vec!["a", "shortcut"]),
+        (3, vec!["a", "b", "c"]),
+        (4, vec!["a", "b", "c"]),
+    ];
+    for (max_morphisms, expected) in expected {
+        let accumulating = HopLimited {
+            optimizer: Accumulating,
+            max_morphisms,
+        }
+        .shortest_path(&staircase::<true>(), 0, 3, ())
+        .safe_unwrap()
+        .unwrap();
+        let negatable = HopLimited {
+            optimizer: Negatable,
+            max_morphisms,
+        }
+        .shortest_path(&staircase::<false>(), 0, 3, ())
+        .unwrap()
+        .unwrap();
+        let infallible = HopLimited {
+            optimizer: NegatableInfallible,
+            max_morphisms,
+        }
+        .shortest_path(&staircase::<false>(), 0, 3, ())
+        .safe_unwrap()
+        .unwrap();
+        let by_rank = HopLimited {
+            optimizer: NegatableByRank(3),
+            max_morphisms,
+        }
+        .shortest_path(&staircase::<false>(), 0, 3, ())
+        .safe_unwrap()
+        .unwrap();
+        assert_eq!(expected, names(accumulating));
+        assert_eq!(expected, names(negatable));
+        assert_eq!(expected, names(infallible));
+        assert_eq!(expected, names(by_rank));
+    }
+    assert!(HopLimited {
+        optimizer: Accumulating,
+        max_morphisms: 0,
+    }
+    .shortest_path(&staircase::<true>(), 0, 3, ())
+    .safe_unwrap()
+    .is_none());
+}
+
+#[test]
+fn negative_cycle_within_limit() {
+    let category = category::<false>(vec![
+        (0, 1, "entry", 1),
+        (1, 2, "there", 1),
+        (2, 1, "back", -3),
+        (1, 3, "exit", 1),
+    ]);
+    assert!(Negatable.shortest_path(&category, 0, 3, ()).is_err());
+
+    let path = HopLimited {
+        optimizer: Negatable,
+        max_morphisms: 4,
+    }
+    .shortest_path(&category, 0, 3, ())
+    .unwrap()
+    .unwrap();
+    assert_eq!(vec!["entry", "there", "back", "exit"], names(path.clone()));
+    assert_eq!(0, path.cost);
+
+    let path = HopLimited {
+        optimizer: Negatable,
+        max_morphisms: 3,
+    }
+    .shortest_path(&category, 0, 3, ())
+    .unwrap()
+    .unwrap();
+    assert_eq!(vec!["entry", "exit"], names(path));
+}
+
+#[test]
+fn missing_objects_match_negatable() {
+    let category = staircase::<false>();
+    let hop_limited = HopLimited {
+        optimizer: Negatable,
+        max_morphisms: 3,
+    };
+    for (source, target) in [(9, 3), (0, 9), (9, 9), (0, 0)] {
+        let expected = Negatable.shortest_path(&category, source, target, ());
+        let result = hop_limited.shortest_path(&category, source, target, ());
+        match (expected, result) {
+            (Ok(None), Ok(None)) => {}
+            (
+                Err(PathFindingError::MissingObject(expected)),
+                Err(PathFindingError::MissingObject(missing)),
+            ) => assert_eq!(expected, missing),
+            (expected, result) => {
+                panic!("{source} -> {target}: expected {expected:?}, got {result:?}")
+            }
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::HashSet;
+
+use crate::category::Category;
+use crate::morphism::{ApplyMorphism, Morphism, MorphismMeta, MorphismOutput};
+use crate::shortest_path::optimizer::Optimizer;
+use crate::shortest_path::path::{AppliedCompositeMorphism, WellFormedPath};
+use crate::{propagation::Accumulate, shortest_path::*, InfallibleResultExt};
+
+/// Multiplies the input by `rate`, and costs `flat` plus `fee` for each unit
+/// of input.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Leg {
+    name: &'static str,
+    rate: i64,
+    flat: i64,
+    fee: i64,
+}
+
+impl MorphismMeta for Leg {
+    type Key = &'static str;
+
+    fn key(&self) -> &&'static str {
+        &self.name
+    }
+}
+
+impl ApplyMorphism<i64, i64, true> for Leg {
+    fn apply(&self, input: i64) -> MorphismOutput<i64, i64> {
+        MorphismOutput {
+            size: input * self.rate,
+            cost: self.flat + self.fee * input,
+        }
+    }
+}
+
+fn flat(source: u8, target: u8, name: &'static str, cost: i64) -> Morphism<u8, Leg> {
+    Morphism::new(
+        source,
+        target,
+        Leg {
+            name,
+            rate: 1,
+            flat: cost,
+            fee: 0,
+        },
+    )
+}
+
+/// Several routes from 0 to 4, with a cycle between 1 and 2.
+fn network() -> Category<u8, Leg> {
+    Category::from_morphisms([
+        flat(0, 1, "a", 1),
+        flat(0, 2, "b", 2),
+        flat(1, 2, "c", 2),
+        flat(2, 1, "d", 1),
+        flat(1, 3, "e", 4),
+        flat(2, 3, "f", 1),
+        flat(3, 4, "g", 1),
+        flat(1, 4, "h", 7),
+        flat(2, 4, "i", 5),
+        flat(0, 3, "j", 6),
+    ])
+}
+
+/// The cost of every path from `at` to `target` that does not revisit an
+/// object, found by brute force.
+fn loopless_costs(
+    category: &Category<u8, Leg>,
+    at: u8,
+    target: u8,
+    visited: &mut Vec<u8>,
+    cost: i64,
+    costs: &mut Vec<i64>,
+) {
+    if at == target {
+        costs.push(cost);
+        return;
+    }
+    visited.push(at);
+    for morphism in category.get_outbound(&at).into_iter().flatten() {
+        if !visited.contains(&morphism.target) {
+            let next_cost = cost + morphism.metadata.flat;
+            loopless_costs(category, morphism.target, target, visited, next_cost, costs);
+        }
+    }
+    visited.pop();
+}
+
+fn names(path: WellFormedPath<u8, Leg, u8, i64, i64>) -> Vec<&'static str> {
+    let applied: AppliedCompositeMorphism<u8, Leg, u8, i64, i64> = path.into();
+    applied
+        .morphisms
+        .iter()
+        .map(|m| m.morphism.metadata.name)
+        .collect()
+}
+
+fn is_loopless(path: &WellFormedPath<u8, Leg, u8, i64, i64>) -> bool {
+    let applied: AppliedCompositeMorphism<u8, Leg, u8, i64, i64> = path.clone().into();
+    let mut objects = HashSet::from([applied.morphisms.first().morphism.source]);
+    applied
+        .morphisms
+        .iter()
+        .all(|m| objects.insert(m.morphism.target))
+}
+
+#[test]
+fn returns_every_loopless_path_in_order_of_cost() {
+    let category = network();
+    let mut expected = vec![];
+    loopless_costs(&category, 0, 4, &mut vec![], 0, &mut expected);
+    expected.sort();
+    assert_eq!(expected.len(), 9);
+
+    let paths = KShortest(100).k_shortest_paths(&category, 0, 4, 1);
+    assert_eq!(
+        paths.iter().map(|path| path.cost).collect::<Vec<_>>(),
+        expected
+    );
+    assert!(paths.iter().all(is_loopless));
+    let distinct = paths.iter().cloned().map(names).collect::<HashSet<_>>();
+    assert_eq!(distinct.len(), paths.len());
+}
+
+#[test]
+fn returns_k_paths_when_there_are_more() {
+    let paths = KShortest(3).k_shortest_paths(&network(), 0, 4, 1);
+    assert_eq!(
+        paths.into_iter().map(names).collect::<Vec<_>>(),
+        vec![
+            vec!["b", "f", "g"],
+            vec!["a", "c", "f", "g"],
+            vec!["a", "e", "g"]
+        ]
+    );
+}
+
+#[test]
+fn negatable_by_rank_is_not_starved_of_alternatives() {
+    let category = network();
+    let mut expected = vec![];
+    loopless_costs(&category, 0, 4, &mut vec![], 0, &mut expected);
+
+    let paths = NegatableByRank(expected.len()).shortest_path_options::<_, _, _, _, _, _, true>(
+        &category,
+        0,
+        4,
+        1,
+        &Accumulate,
+    );
+    assert_eq!(paths.len(), expected.len());
+
+    let best = NegatableByRank(expected.len())
+        .shortest_path(&category, 0, 4, 1)
+        .safe_unwrap()
+        .unwrap();
+    assert_eq!(best.cost, 4);
+}
+
+#[test]
+fn deviations_start_from_the_accumulated_size() {
+    let leg = |source, target, name, rate, flat, fee| {
+        Morphism::new(
+            source,
+            target,
+            Leg {
+                name,
+                rate,
+                flat,
+                fee,
+            },
+        )
+    };
+    let category = Category::from_morphisms([
+        leg(0, 1, "double", 2, 0, 0),
+        leg(1, 2, "fee", 1, 0, 1),
+        leg(1, 2, "flat", 1, 3, 0),
+        leg(0, 2, "direct", 1, 10, 0),
+    ]);
+
+    let paths = KShortest(5).k_shortest_paths(&category, 0, 2, 1);
+    assert_eq!(
+        paths.iter().map(|path| path.cost).collect::<Vec<_>>(),
+        vec![2, 3, 10]
+    );
+    assert_eq!(
+        paths.into_iter().map(names).collect::<Vec<_>>(),
+        vec![
+            vec!["double", "fee"],
+            vec!["double", "flat"],
+            vec!["direct"]
+        ]
+    );
+}
@@ -0,0 +1,168 @@
+use std::cmp::Reverse;
+
+use crate::category::Category;
+use crate::impls::Float;
+use crate::morphism::{ApplyMorphism, Morphism, MorphismMeta, MorphismOutput};
+use crate::shortest_path::optimizer::{score, Optimizer};
+use crate::shortest_path::path::{AppliedCompositeMorphism, WellFormedPath};
+use crate::shortest_path::*;
+
+/// Deducts a flat `fee` from the input, then exchanges the rest at `rate`, up
+/// to the `cap` if there is one. The fee is also the cost.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Swap {
+    name: &'static str,
+    rate: Float,
+    fee: Float,
+    cap: Option<Float>,
+}
+
+impl MorphismMeta for Swap {
+    type Key = &'static str;
+
+    fn key(&self) -> &&'static str {
+        &self.name
+    }
+}
+
+impl ApplyMorphism for Swap {
+    fn apply(&self, input: Float) -> MorphismOutput {
+        let size = (input - self.fee) * self.rate;
+        MorphismOutput {
+            size: self.cap.map_or(size, |cap| size.min(cap)),
+            cost: self.fee,
+        }
+    }
+}
+
+fn swap(source: u8, target: u8, name: &'static str, rate: f64, fee: i32) -> Morphism<u8, Swap> {
+    Morphism::new(
+        source,
+        target,
+        Swap {
+            name,
+            rate: rate.into(),
+            fee: fee.into(),
+            cap: None,
+        },
+    )
+}
+
+fn capped(source: u8, target: u8, name: &'static str, rate: f64, cap: i32) -> Morphism<u8, Swap> {
+    let mut morphism = swap(source, target, name, rate, 0);
+    morphism.metadata.cap = Some(cap.into());
+    morphism
+}
+
+/// The direct swap is the cheapest, but the indirect route outputs more.
+/// Swapping back to 0 always loses value.
+fn category(extra: Vec<Morphism<u8, Swap>>) -> Category<u8, Swap> {
+    let mut category = Category::new();
+    category.add_objects(vec![0, 1, 2, 3, 4]).unwrap();
+    category
+        .add_morphisms(vec![
+            swap(0, 2, "direct", 1.5, 1),
+            swap(0, 1, "first", 2.0, 2),
+            swap(1, 2, "second", 1.0, 1),
+            swap(2, 0, "back", 0.5, 0),
+        ])
+        .unwrap();
+    category.add_morphisms(extra).unwrap();
+    category
+}
+
+fn names(path: &WellFormedPath<u8, Swap>) -> Vec<&'static str> {
+    let applied: AppliedCompositeMorphism<u8, Swap> = path.clone().into();
+    applied
+        .morphisms
+        .iter()
+        .map(|m| m.morphism.metadata.name)
+        .collect()
+}
+
+#[test]
+fn selects_largest_output() {
+    let category = category(vec![]);
+    let path = MaxOutput
+        .shortest_path(&category, 0, 2, 10.into())
+        .unwrap()
+        .unwrap();
+    assert_eq!(vec!["first", "second"], names(&path));
+    assert_eq!(Reverse(Float::from(15)), score::output(&path));
+    assert_eq!(Float::from(3), path.cost);
+
+    let cheapest = Negatable
+        .shortest_path(&category, 0, 2, 10.into())
+        .unwrap()
+        .unwrap();
+    assert_eq!(vec!["direct"], names(&cheapest));
+}
+
+#[test]
+fn ranks_by_output() {
+    let ranked = MaxOutput
+        .ranked_paths::<_, WellFormedPath<u8, Swap>, _>(
+            &category(vec![]),
+            vec![(0, 10.into())],
+            vec![2, 1],
+            score::output,
+        )
+        .unwrap();
+    let outputs = ranked.iter().map(|path| path.cost.0).collect::<Vec<_>>();
+    assert_eq!(vec![Float::from(16), Float::from(15)], outputs);
+}
+
+#[test]
+fn unbounded_cycle_is_an_error() {
+    // 3 and 4 can be reached, but they cannot reach the target
+    let unreachable = category(vec![
+        swap(0, 3, "out", 1.0, 0),
+        swap(3, 4, "x", 2.0, 0),
+        swap(4, 3, "y", 1.0, 0),
+    ]);
+    let path = MaxOutput
+        .shortest_path(&unreachable, 0, 2, 10.into())
+        .unwrap()
+        .unwrap();
+    assert_eq!(vec!["first", "second"], names(&path));
+
+    let reachable = category(vec![swap(1, 3, "x", 2.0, 0), swap(3, 1, "y", 1.0, 0)]);
+    let Err(PathFindingError::UnboundedOutput(cycle)) =
+        MaxOutput.shortest_path(&reachable, 0, 2, 10.into())
+    else {
+        panic!("expected an unbounded cycle");
+    };
+    let mut cycle = cycle.iter().map(|m| m.metadata.name).collect::<Vec<_>>();
+    cycle.sort();
+    assert_eq!(vec!["x", "y"], cycle);
+}
+
+#[test]
+fn bounded_cycle_is_traversed() {
+    // the cycle doubles the size until it reaches the cap. the extra objects
+    // leave enough rounds for it to settle, so the predecessors of 1 and 3
+    // point at each other
+    let mut category = category(vec![capped(1, 3, "x", 2.0, 40), swap(3, 1, "y", 1.0, 0)]);
+    category.add_objects(vec![5, 6, 7, 8, 9]).unwrap();
+    let path = MaxOutput
+        .shortest_path(&category, 0, 2, 10.into())
+        .unwrap()
+        .unwrap();
+    assert_eq!(vec!["first", "x", "y", "x", "y", "second"], names(&path));
+    assert_eq!(Reverse(Float::from(39)), score::output(&path));
+}
+
+#[test]
+fn slowly_bounded_cycle_is_not_unbounded() {
+    // the cycle is still increasing the size after a round per object
+    let category = category(vec![capped(1, 3, "x", 1.5, 1000), swap(3, 1, "y", 1.0, 0)]);
+    let path = MaxOutput
+        .shortest_path(&category, 0, 2, 10.into())
+        .unwrap()
+        .unwrap();
+    let names = names(&path);
+    assert_eq!(Some(&"first"), names.first());
+    assert_eq!(Some(&"second"), names.last());
+    assert!(names.len() > 2 * category.objects().len());
+    assert_eq!(Reverse(Float::from(999)), score::output(&path));
+}
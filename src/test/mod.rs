@@ -1,4 +1,88 @@
 #![cfg(test)]
 
+mod all_pairs;
+mod bidirectional;
+mod category;
+mod compiled;
+mod context;
+mod cost;
 mod custom_types;
+#[allow(clippy::useless_conversion)]
 mod default_types;
+mod dot;
+mod fallible;
+mod hop_limited;
+mod k_shortest;
+mod max_output;
+mod negative_costs;
+mod pareto;
+mod path;
+mod propagation;
+mod query;
+mod resource_constrained;
+#[cfg(feature = "serde")]
+mod serialization;
+mod split;
+mod tree;
+
+use std::cell::Cell;
+
+use crate::category::Category;
+use crate::morphism::{ApplyMorphism, Morphism, MorphismMeta, MorphismOutput};
+use crate::shortest_path::path::{AppliedCompositeMorphism, WellFormedPath};
+
+thread_local! {
+    /// Counts every application of a Quote in the current test's thread, to
+    /// show when morphisms are applied.
+    static APPLIED: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Multiplies the input by `rate`, and costs `fee` for each unit of input.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Quote<const NON_NEGATIVE: bool = false> {
+    name: &'static str,
+    rate: i64,
+    fee: i64,
+}
+
+impl<const NON_NEGATIVE: bool> MorphismMeta for Quote<NON_NEGATIVE> {
+    type Key = &'static str;
+
+    fn key(&self) -> &&'static str {
+        &self.name
+    }
+}
+
+impl<const NON_NEGATIVE: bool> ApplyMorphism<i64, i64, NON_NEGATIVE> for Quote<NON_NEGATIVE> {
+    fn apply(&self, input: i64) -> MorphismOutput<i64, i64> {
+        APPLIED.set(APPLIED.get() + 1);
+        MorphismOutput {
+            size: input * self.rate,
+            cost: self.fee * input,
+        }
+    }
+}
+
+/// A category of quotes, each given as (source, target, name, rate, fee).
+fn quotes<const NON_NEGATIVE: bool>(
+    quotes: Vec<(u8, u8, &'static str, i64, i64)>,
+) -> Category<u8, Quote<NON_NEGATIVE>> {
+    Category::from_morphisms(quotes.into_iter().map(|(source, target, name, rate, fee)| {
+        Morphism::new(source, target, Quote { name, rate, fee })
+    }))
+}
+
+/// The name of each quote in the path, and the total cost.
+fn quote_names<const NON_NEGATIVE: bool>(
+    path: WellFormedPath<u8, Quote<NON_NEGATIVE>, u8, i64, i64>,
+) -> (Vec<&'static str>, i64) {
+    let applied: AppliedCompositeMorphism<u8, Quote<NON_NEGATIVE>, u8, i64, i64> = path.into();
+    (
+        applied
+            .morphisms
+            .iter()
+            .map(|m| m.morphism.metadata.name)
+            .collect(),
+        applied.cost,
+    )
+}
@@ -0,0 +1,255 @@
+use crate::category::Category;
+use crate::collections::SomeVec;
+use crate::impls::Float;
+use crate::morphism::{ApplyMorphism, Morphism, MorphismMeta, MorphismOutput};
+use crate::shortest_path::optimizer::Optimizer;
+use crate::shortest_path::*;
+use crate::vertex::Vertex;
+use crate::InfallibleResultExt;
+
+/// cost = constant + rate * input, size = input * growth
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Step {
+    name: &'static str,
+    constant: Float,
+    rate: Float,
+    growth: Float,
+}
+
+impl MorphismMeta for Step {
+    type Key = &'static str;
+
+    fn key(&self) -> &&'static str {
+        &self.name
+    }
+}
+
+impl ApplyMorphism<Float, Float, false> for Step {
+    fn apply(&self, input: Float) -> MorphismOutput {
+        MorphismOutput {
+            size: input * self.growth,
+            cost: self.constant + self.rate * input,
+        }
+    }
+}
+
+fn step(
+    source: u8,
+    target: u8,
+    name: &'static str,
+    constant: i32,
+    rate: i32,
+    growth: i32,
+) -> Morphism<u8, Step> {
+    Morphism::new(
+        source,
+        target,
+        Step {
+            name,
+            constant: constant.into(),
+            rate: rate.into(),
+            growth: growth.into(),
+        },
+    )
+}
+
+/// Both paths look equally good without accumulation, but the path through 2
+/// triples the size before a rebate that is proportional to the size.
+fn rebates() -> Category<u8, Step> {
+    vec![
+        step(0, 1, "a", 1, 0, 1),
+        step(1, 3, "c", 0, -1, 1),
+        step(0, 2, "b", 1, 0, 3),
+        step(2, 3, "d", 0, -1, 1),
+    ]
+    .into()
+}
+
+fn unbounded() -> LabelCorrecting {
+    LabelCorrecting {
+        max_hops: 100,
+        max_states: 1000,
+    }
+}
+
+#[test]
+fn label_correcting_accumulates_negative_costs() {
+    let path = unbounded()
+        .shortest_path(&rebates(), 0, 3, 10.into())
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(Float::from(-29), path.cost);
+    assert!(path.vertices.iter().any(|v| v.is_object_with_id(&2)));
+    assert_eq!(5, path.vertices.len());
+    let step_costs: Vec<_> = path.vertices.iter().filter_map(Vertex::cost).collect();
+    assert_eq!(vec![&Float::from(1), &Float::from(-30)], step_costs);
+}
+
+#[test]
+fn label_correcting_negative_cycle() {
+    let category = vec![
+        step(0, 1, "a", 1, 0, 1),
+        step(1, 0, "b", -2, 0, 1),
+        step(1, 2, "c", 1, 0, 1),
+    ]
+    .into();
+    let result = unbounded().shortest_path(&category, 0, 2, 10.into());
+
+    let Err(PathFindingError::NegativeCycle(cycle)) = result else {
+        panic!("expected a negative cycle, got {result:?}");
+    };
+    assert_eq!(vec!["a", "b"], sorted_names(&cycle));
+}
+
+/// 1 -> 2 -> 3 -> 1 is a negative cycle that is unreachable from 0. 4 -> 5 -> 4
+/// is a cycle with a total cost of zero.
+fn arbitrage() -> Category<u8, Step> {
+    vec![
+        step(0, 1, "entry", 1, 0, 1),
+        step(1, 2, "x", 1, 0, 1),
+        step(2, 3, "y", 1, 0, 1),
+        step(3, 1, "z", -3, 0, 1),
+        step(3, 0, "exit", 1, 0, 1),
+        step(4, 5, "p", -1, 0, 1),
+        step(5, 4, "q", 1, 0, 1),
+    ]
+    .into()
+}
+
+fn sorted_names(cycle: &SomeVec<Morphism<u8, Step>>) -> Vec<&'static str> {
+    let mut names: Vec<_> = cycle.iter().map(|m| m.metadata.name).collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn negatable_returns_negative_cycle() {
+    let result = Negatable.shortest_path(&arbitrage(), 0, 2, 10.into());
+
+    let Err(PathFindingError::NegativeCycle(cycle)) = result else {
+        panic!("expected a negative cycle, got {result:?}");
+    };
+    // "exit" and "entry" form a positive cycle with part of the negative cycle
+    assert_eq!(vec!["x", "y", "z"], sorted_names(&cycle));
+    for (morphism, next) in cycle.iter().zip(cycle.iter().cycle().skip(1)) {
+        assert_eq!(morphism.target, next.source);
+    }
+}
+
+#[test]
+fn find_negative_cycles_without_source() {
+    let cycles = find_negative_cycles(&arbitrage(), Float::from(10));
+
+    assert_eq!(1, cycles.len());
+    assert_eq!(vec!["x", "y", "z"], sorted_names(&cycles[0]));
+
+    let cycles = find_negative_cycles(&rebates(), Float::from(10));
+    assert!(cycles.is_empty());
+}
+
+#[test]
+fn label_correcting_is_bounded() {
+    // every lap around the cycle doubles the size, so there is no end to the
+    // number of distinct states.
+    let category = vec![
+        step(0, 1, "a", 1, 0, 2),
+        step(1, 0, "b", 1, 0, 1),
+        step(1, 2, "c", 100, 0, 1),
+    ]
+    .into();
+    let result = LabelCorrecting {
+        max_hops: usize::MAX,
+        max_states: 10,
+    }
+    .shortest_path(&category, 0, 2, 10.into());
+    assert!(matches!(
+        result,
+        Err(PathFindingError::StateBudgetExceeded(10))
+    ));
+
+    let path = LabelCorrecting {
+        max_hops: 10,
+        max_states: 1000,
+    }
+    .shortest_path(&category, 0, 2, 10.into())
+    .unwrap()
+    .unwrap();
+    assert_eq!(Float::from(101), path.cost);
+}
+
+/// Integer costs that do not depend on size
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Fee(&'static str, i64);
+
+impl MorphismMeta for Fee {
+    type Key = &'static str;
+
+    fn key(&self) -> &&'static str {
+        &self.0
+    }
+}
+
+impl ApplyMorphism<(), i64, false> for Fee {
+    fn apply(&self, _input: ()) -> MorphismOutput<(), i64> {
+        MorphismOutput {
+            size: (),
+            cost: self.1,
+        }
+    }
+}
+
+#[test]
+fn integer_costs_work_with_every_backend() {
+    // 3 is unreachable from 0, so its rebate must not be used
+    let category: Category<u8, Fee> = vec![
+        Morphism::new(0, 1, Fee("a", 5)),
+        Morphism::new(1, 2, Fee("b", -3)),
+        Morphism::new(0, 2, Fee("c", 3)),
+        Morphism::new(3, 2, Fee("d", -10)),
+    ]
+    .into();
+
+    let bellman_ford = Negatable
+        .shortest_path(&category, 0, 2, ())
+        .unwrap()
+        .unwrap();
+    assert_eq!(2, bellman_ford.cost);
+    assert_eq!(5, bellman_ford.vertices.len());
+
+    let label_correcting = unbounded()
+        .shortest_path(&category, 0, 2, ())
+        .unwrap()
+        .unwrap();
+    assert_eq!(2, label_correcting.cost);
+
+    let ranked = NegatableByRank(3).shortest_path(&category, 0, 2, ());
+    assert!(ranked.safe_unwrap().is_some());
+
+    assert!(find_negative_cycles(&category, ()).is_empty());
+}
+
+#[test]
+fn label_correcting_respects_max_hops() {
+    // 0 -> 2 -> 1 is the cheapest way to reach 1, but it leaves no hops for
+    // "d" when max_hops is 2
+    let category: Category<u8, Step> = vec![
+        step(0, 1, "a", 5, 0, 1),
+        step(0, 2, "b", 0, 0, 1),
+        step(2, 1, "c", 1, 0, 1),
+        step(1, 3, "d", 1, 0, 1),
+    ]
+    .into();
+
+    for (max_hops, expected) in [(2, 6), (3, 2)] {
+        let path = LabelCorrecting {
+            max_hops,
+            max_states: 1000,
+        }
+        .shortest_path(&category, 0, 3, 10.into())
+        .unwrap()
+        .unwrap();
+        assert!(path.vertices.len() <= 2 * max_hops + 1);
+        assert_eq!(Float::from(expected), path.cost);
+    }
+}
@@ -0,0 +1,173 @@
+use crate::category::Category;
+use crate::cost::VectorCost;
+use crate::impls::Float;
+use crate::morphism::{ApplyMorphism, Morphism, MorphismMeta, MorphismOutput};
+use crate::propagation::Accumulate;
+use crate::shortest_path::path::{AppliedCompositeMorphism, WellFormedPath};
+use crate::shortest_path::*;
+
+type FeeAndHops = (Float, u32);
+
+/// Charges `fee` for every unit of input, and multiplies the size by `rate`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Route {
+    name: &'static str,
+    fee: Float,
+    rate: Float,
+}
+
+impl MorphismMeta for Route {
+    type Key = &'static str;
+
+    fn key(&self) -> &&'static str {
+        &self.name
+    }
+}
+
+impl ApplyMorphism<Float, FeeAndHops, true> for Route {
+    fn apply(&self, input: Float) -> MorphismOutput<Float, FeeAndHops> {
+        MorphismOutput {
+            size: input * self.rate,
+            cost: (input * self.fee, 1),
+        }
+    }
+}
+
+fn category() -> Category<u8, Route> {
+    let mut category = Category::new();
+    category.add_objects(vec![0, 1, 2, 3]).unwrap();
+    let route = |source, target, name, fee: i32, rate: i32| {
+        Morphism::new(
+            source,
+            target,
+            Route {
+                name,
+                fee: fee.into(),
+                rate: rate.into(),
+            },
+        )
+    };
+    category
+        .add_morphisms(vec![
+            route(0, 2, "direct", 10, 1),
+            route(0, 1, "doubling", 1, 2),
+            route(0, 1, "expensive", 5, 1),
+            route(1, 2, "short", 1, 1),
+            route(1, 3, "free", 0, 1),
+            route(3, 2, "also free", 0, 1),
+            route(2, 0, "back", 0, 1),
+        ])
+        .unwrap();
+    category
+}
+
+fn names(path: &WellFormedPath<u8, Route, u8, Float, FeeAndHops>) -> Vec<&'static str> {
+    let applied: AppliedCompositeMorphism<u8, Route, u8, Float, FeeAndHops> = path.clone().into();
+    applied
+        .morphisms
+        .iter()
+        .map(|m| m.morphism.metadata.name)
+        .collect()
+}
+
+#[test]
+fn returns_every_non_dominated_path() {
+    let mut front = ParetoOptimizer.pareto_front(&category(), 0, 2, Float::from(1));
+    front.sort_by_key(|path| path.cost.1);
+
+    let routes = front.iter().map(names).collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            vec!["direct"],
+            vec!["doubling", "short"],
+            vec!["doubling", "free", "also free"],
+        ],
+        routes
+    );
+    // the second fee is charged on the accumulated size
+    let costs = front.iter().map(|path| path.cost).collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            (Float::from(10), 1),
+            (Float::from(3), 2),
+            (Float::from(1), 3)
+        ],
+        costs
+    );
+    for path in &front {
+        for other in &front {
+            assert!(!path.cost.dominates(&other.cost));
+        }
+    }
+}
+
+#[test]
+fn excluded_morphisms_are_skipped() {
+    let mut front = ParetoOptimizer.pareto_front_filtered(
+        &category(),
+        0,
+        2,
+        Float::from(1),
+        &Accumulate,
+        &|m: &Morphism<u8, Route>| m.metadata.name != "doubling",
+    );
+    front.sort_by_key(|path| path.cost.1);
+
+    let routes = front.iter().map(names).collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            vec!["direct"],
+            vec!["expensive", "short"],
+            vec!["expensive", "free", "also free"],
+        ],
+        routes
+    );
+}
+
+#[test]
+fn vector_cost_dominance() {
+    assert!([1, 2].dominates(&[1, 3]));
+    assert!(![1, 2].dominates(&[1, 2]));
+    assert!([1, 2].weakly_dominates(&[1, 2]));
+    assert!(![1, 4].dominates(&[2, 3]));
+    assert!(![2, 3].dominates(&[1, 4]));
+    assert_eq!((3, 5), (1, 2).combine(&(2, 3)));
+}
+
+/// A fee that does not depend on the size.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Fee(&'static str, i64);
+
+impl MorphismMeta for Fee {
+    type Key = &'static str;
+
+    fn key(&self) -> &&'static str {
+        &self.0
+    }
+}
+
+impl ApplyMorphism<(), [i64; 1]> for Fee {
+    fn apply(&self, _input: ()) -> MorphismOutput<(), [i64; 1]> {
+        MorphismOutput {
+            size: (),
+            cost: [self.1],
+        }
+    }
+}
+
+#[test]
+fn dominated_labels_are_kept_if_they_can_reach_more_objects() {
+    // 0 -> 1 -> 2 reaches 2 more cheaply than 0 -> 2, but it cannot use the
+    // rebate from 2 back to 1
+    let category: Category<u8, Fee> = vec![
+        Morphism::new(0, 1, Fee("a", 0)),
+        Morphism::new(0, 2, Fee("b", 1)),
+        Morphism::new(1, 2, Fee("c", 0)),
+        Morphism::new(2, 1, Fee("d", -10)),
+        Morphism::new(1, 3, Fee("e", 0)),
+    ]
+    .into();
+    let front = ParetoOptimizer.pareto_front(&category, 0, 3, ());
+    assert_eq!(front.len(), 1);
+    assert_eq!(front[0].cost, [-9]);
+}
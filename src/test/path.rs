@@ -0,0 +1,111 @@
+use std::convert::Infallible;
+
+use crate::morphism::Morphism;
+use crate::shortest_path::path::{reapply, InvalidPath, Path, WellFormedPath};
+use crate::shortest_path::PathFindingError;
+use crate::test::Quote;
+use crate::vertex::Vertex;
+
+type QuoteVertex = Vertex<u8, Quote, u8, i64, i64>;
+
+fn object(id: u8) -> QuoteVertex {
+    Vertex::Object { inner: id, size: 1 }
+}
+
+fn morphism(source: u8, target: u8) -> QuoteVertex {
+    Vertex::Morphism {
+        inner: Morphism::new(
+            source,
+            target,
+            Quote {
+                name: "quote",
+                rate: 1,
+                fee: 1,
+            },
+        ),
+        input: 1,
+        cost: 1,
+    }
+}
+
+fn well_formed(vertices: Vec<QuoteVertex>) -> Result<(), InvalidPath> {
+    let path = Path {
+        vertices: vertices.try_into().unwrap(),
+        cost: 1,
+    };
+    WellFormedPath::try_from(path).map(|_| ())
+}
+
+fn reapplied(vertices: Vec<QuoteVertex>) -> Result<i64, PathFindingError<u8, Quote, Infallible>> {
+    reapply::<_, _, _, _, i64, _, _, false>(vertices, 1, &crate::propagation::Accumulate)
+        .map(|(_, cost)| cost)
+}
+
+#[test]
+fn path_structure_errors() {
+    assert!(well_formed(vec![object(0), morphism(0, 1), object(1)]).is_ok());
+    assert!(matches!(
+        well_formed(vec![morphism(0, 1), morphism(0, 1), object(1)]),
+        Err(InvalidPath::SourceIsNotObject)
+    ));
+    assert!(matches!(
+        well_formed(vec![object(0), object(1), object(1)]),
+        Err(InvalidPath::InnerIsNotMorphism)
+    ));
+    assert!(matches!(
+        well_formed(vec![
+            object(0),
+            morphism(0, 1),
+            morphism(1, 2),
+            morphism(1, 2),
+            object(2)
+        ]),
+        Err(InvalidPath::InnerIsNotObject)
+    ));
+    assert!(matches!(
+        well_formed(vec![object(0), morphism(0, 1), morphism(0, 1)]),
+        Err(InvalidPath::TargetIsNotObject)
+    ));
+}
+
+#[test]
+fn morphisms_must_connect_their_objects() {
+    assert!(matches!(
+        well_formed(vec![object(0), morphism(0, 2), object(1)]),
+        Err(InvalidPath::DisconnectedMorphism(0))
+    ));
+    assert!(matches!(
+        well_formed(vec![
+            object(0),
+            morphism(0, 1),
+            object(1),
+            morphism(2, 3),
+            object(3)
+        ]),
+        Err(InvalidPath::DisconnectedMorphism(1))
+    ));
+}
+
+#[test]
+fn reapply_reports_malformed_paths() {
+    assert_eq!(
+        1,
+        reapplied(vec![object(0), morphism(0, 1), object(1)]).unwrap()
+    );
+    assert!(matches!(
+        reapplied(vec![morphism(0, 1), object(1)]),
+        Err(PathFindingError::InvalidPath(
+            InvalidPath::SourceIsNotObject
+        ))
+    ));
+    assert!(matches!(
+        reapplied(vec![object(0), morphism(0, 1)]),
+        Err(PathFindingError::InvalidPath(
+            InvalidPath::TargetIsNotObject
+        ))
+    ));
+    assert!(matches!(
+        reapplied(vec![object(0), morphism(0, 1), morphism(1, 2), object(2)]),
+        Err(PathFindingError::InvalidPath(InvalidPath::InnerIsNotObject))
+    ));
+}
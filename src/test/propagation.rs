@@ -0,0 +1,128 @@
+use crate::category::Category;
+use crate::impls::{DeductiveLinearCost, Float, SimpleMorphism};
+use crate::morphism::Morphism;
+use crate::propagation::{Combine, ConstantPerObject, PassThrough};
+use crate::shortest_path::optimizer::Optimizer;
+use crate::shortest_path::path::{AppliedCompositeMorphism, WellFormedPath};
+use crate::vertex::Vertex;
+use crate::{shortest_path::*, InfallibleResultExt};
+
+type MyMorphMeta = SimpleMorphism<String, DeductiveLinearCost>;
+
+fn step(source: u8, target: u8, rate: f64, constant: f64) -> Morphism<u8, MyMorphMeta> {
+    Morphism::new(
+        source,
+        target,
+        MyMorphMeta {
+            meta: format!("{source}to{target}"),
+            logic: DeductiveLinearCost {
+                rate: rate.into(),
+                constant: constant.into(),
+            },
+        },
+    )
+}
+
+/// 0 -> 1 -> 2 costs 19 with accumulation, but only 10 if object 1 is empty.
+/// 0 -> 3 -> 2 always costs 13.
+fn category() -> Category<u8, MyMorphMeta> {
+    vec![
+        step(0, 1, 0.1, 0.0),
+        step(1, 2, 0.1, 0.0),
+        step(0, 3, 0.0, 12.0),
+        step(3, 2, 0.0, 1.0),
+    ]
+    .into()
+}
+
+fn empty_one(id: &u8) -> Float {
+    if *id == 1 {
+        0.into()
+    } else {
+        100.into()
+    }
+}
+
+fn sizes(path: &WellFormedPath<u8, MyMorphMeta>) -> Vec<Float> {
+    path.vertices
+        .iter()
+        .filter_map(|v| match v {
+            Vertex::Object { size, .. } => Some(*size),
+            Vertex::Morphism { .. } => None,
+        })
+        .collect()
+}
+
+#[test]
+fn propagation_changes_path_selection() {
+    let accumulated = Accumulating
+        .shortest_path(&category(), 0, 2, 100.into())
+        .safe_unwrap()
+        .unwrap();
+    assert!(accumulated.vertices.iter().any(|v| v.is_object_with_id(&3)));
+    assert_eq!(Float::from(13), accumulated.cost);
+
+    let constant = ConstantPerObject(empty_one);
+    let label_correcting = LabelCorrecting {
+        max_hops: 10,
+        max_states: 100,
+    };
+    for path in [
+        Accumulating
+            .shortest_path_propagating(&category(), 0, 2, 100.into(), &constant)
+            .safe_unwrap()
+            .unwrap(),
+        label_correcting
+            .shortest_path_propagating(&category(), 0, 2, 100.into(), &constant)
+            .unwrap()
+            .unwrap(),
+    ] {
+        assert_eq!(Float::from(10), path.cost);
+        let expected: Vec<Float> = vec![100.into(), 0.into(), 100.into()];
+        assert_eq!(expected, sizes(&path));
+    }
+}
+
+#[test]
+fn propagation_is_applied_to_returned_sizes() {
+    let negatable = Negatable
+        .shortest_path_propagating(&category(), 0, 2, 100.into(), &ConstantPerObject(empty_one))
+        .unwrap()
+        .unwrap();
+    assert!(negatable.vertices.iter().any(|v| v.is_object_with_id(&3)));
+    let expected: Vec<Float> = vec![100.into(), 100.into(), 100.into()];
+    assert_eq!(expected, sizes(&negatable));
+
+    let applied: AppliedCompositeMorphism<u8, MyMorphMeta> = Accumulating
+        .shortest_path_propagating(&category(), 0, 2, 100.into(), &PassThrough)
+        .safe_unwrap()
+        .unwrap()
+        .into();
+    assert_eq!(Float::from(13), applied.cost);
+
+    let chain: AppliedCompositeMorphism<u8, MyMorphMeta> = Negatable
+        .shortest_path(
+            &vec![step(0, 1, 0.1, 0.0), step(1, 2, 0.1, 0.0)].into(),
+            0,
+            2,
+            100.into(),
+        )
+        .unwrap()
+        .unwrap()
+        .into();
+    assert_eq!(Float::from(19), chain.cost);
+    assert_eq!(Float::from(81), chain.output());
+
+    let passed = chain
+        .clone()
+        .reapply_propagating::<_, true>(100.into(), &PassThrough);
+    assert_eq!(Float::from(20), passed.cost);
+    assert_eq!(Float::from(100), passed.output());
+
+    let combined = chain.reapply_propagating::<_, true>(
+        100.into(),
+        &Combine(|input: &Float, output: Float| *input + output),
+    );
+    assert_eq!(Float::from(29), combined.cost);
+    assert_eq!(Float::from(190), combined.morphisms.first().target.1);
+}
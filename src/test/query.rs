@@ -0,0 +1,278 @@
+use crate::category::Category;
+use crate::morphism::{ApplyMorphism, InputIndependent, Morphism, MorphismMeta, MorphismOutput};
+use crate::propagation::{Accumulate, PassThrough};
+use crate::shortest_path::optimizer::Optimizer;
+use crate::shortest_path::path::{AppliedCompositeMorphism, WellFormedPath};
+use crate::shortest_path::query::Query;
+use crate::test::{quote_names, quotes};
+use crate::{shortest_path::*, InfallibleResultExt};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Toll {
+    name: &'static str,
+    family: &'static str,
+    cost: i64,
+}
+
+impl MorphismMeta for Toll {
+    type Key = &'static str;
+
+    fn key(&self) -> &&'static str {
+        &self.name
+    }
+}
+
+impl ApplyMorphism<(), i64, true> for Toll {
+    fn apply(&self, _input: ()) -> MorphismOutput<(), i64> {
+        assert_ne!("closed", self.family, "an excluded morphism was applied");
+        MorphismOutput {
+            size: (),
+            cost: self.cost,
+        }
+    }
+}
+
+impl InputIndependent for Toll {}
+
+fn category() -> Category<u8, Toll> {
+    let toll = |source, target, name, family, cost| {
+        Morphism::new(source, target, Toll { name, family, cost })
+    };
+    Category::from_morphisms(vec![
+        toll(0, 3, "direct", "road", 10),
+        toll(0, 1, "a", "bridge", 1),
+        toll(1, 3, "b", "road", 1),
+        toll(0, 2, "c", "bridge", 3),
+        toll(2, 3, "d", "road", 2),
+        toll(1, 2, "e", "road", 1),
+    ])
+}
+
+fn names(path: WellFormedPath<u8, Toll, u8, (), i64>) -> Vec<&'static str> {
+    let applied: AppliedCompositeMorphism<u8, Toll, u8, (), i64> = path.into();
+    applied
+        .morphisms
+        .iter()
+        .map(|m| m.morphism.metadata.name)
+        .collect()
+}
+
+fn query(query: Query<u8, Toll, ()>) -> Option<(Vec<&'static str>, i64)> {
+    let category = category();
+    let accumulating = Accumulating
+        .shortest_path_query(&category, &query)
+        .safe_unwrap();
+    let negatable = Negatable.shortest_path_query(&category, &query).unwrap();
+    assert_eq!(
+        accumulating.as_ref().map(|path| path.cost),
+        negatable.as_ref().map(|path| path.cost)
+    );
+    accumulating.map(|path| {
+        let cost = path.cost;
+        (names(path), cost)
+    })
+}
+
+#[test]
+fn waypoints_are_visited_in_order() {
+    assert_eq!(Some((vec!["a", "b"], 2)), query(Query::new(0, 3, ())));
+    assert_eq!(
+        Some((vec!["a", "e", "d"], 4)),
+        query(Query::new(0, 3, ()).via(2))
+    );
+    assert_eq!(
+        Some((vec!["a", "e", "d"], 4)),
+        query(Query::new(0, 3, ()).via(1).via(2))
+    );
+    assert_eq!(None, query(Query::new(0, 3, ()).via(2).via(1)));
+    assert_eq!(
+        Some((vec!["a", "b"], 2)),
+        query(Query::new(0, 3, ()).via(0))
+    );
+}
+
+#[test]
+fn exclusions_only_apply_to_the_query() {
+    assert_eq!(
+        Some((vec!["c", "d"], 5)),
+        query(Query::new(0, 3, ()).avoiding(1))
+    );
+    assert_eq!(
+        Some((vec!["direct"], 10)),
+        query(
+            Query::new(0, 3, ()).excluding(|m: &Morphism<u8, Toll>| m.metadata.family == "bridge")
+        )
+    );
+    assert_eq!(
+        Some((vec!["a", "e", "d"], 4)),
+        query(Query::new(0, 3, ()).excluding(|m: &Morphism<u8, Toll>| m.metadata.name == "b"))
+    );
+    assert_eq!(None, query(Query::new(0, 3, ()).via(2).avoiding(2)));
+    assert_eq!(None, query(Query::new(0, 3, ()).avoiding(3)));
+    assert_eq!(6, category().morphisms().len());
+}
+
+#[test]
+fn excluded_morphisms_are_never_applied() {
+    let mut category = category();
+    for (source, target, name) in [(0, 2, "ferry"), (2, 3, "tunnel")] {
+        let closed = Toll {
+            name,
+            family: "closed",
+            cost: 0,
+        };
+        category
+            .add_morphism(Morphism::new(source, target, closed))
+            .unwrap();
+    }
+    let query = Query::new(0, 3, ())
+        .via(2)
+        .excluding(|m: &Morphism<u8, Toll>| m.metadata.family == "closed");
+    let cost = |path: Option<WellFormedPath<u8, Toll, u8, (), i64>>| path.map(|path| path.cost);
+
+    let expected = Some(4);
+    assert_eq!(
+        expected,
+        cost(
+            Accumulating
+                .shortest_path_query(&category, &query)
+                .safe_unwrap()
+        )
+    );
+    assert_eq!(
+        expected,
+        cost(
+            AStar(|_: &u8, _: &()| 0)
+                .shortest_path_query(&category, &query)
+                .safe_unwrap()
+        )
+    );
+    assert_eq!(
+        expected,
+        cost(
+            Bidirectional
+                .shortest_path_query(&category, &query)
+                .unwrap()
+        )
+    );
+    assert_eq!(
+        expected,
+        cost(Negatable.shortest_path_query(&category, &query).unwrap())
+    );
+    assert_eq!(
+        expected,
+        cost(
+            KShortest(3)
+                .shortest_path_query(&category, &query)
+                .safe_unwrap()
+        )
+    );
+    assert_eq!(
+        expected,
+        cost(
+            LabelCorrecting {
+                max_hops: 5,
+                max_states: 100,
+            }
+            .shortest_path_query(&category, &query)
+            .unwrap()
+        )
+    );
+    assert_eq!(
+        expected,
+        cost(
+            HopLimited {
+                optimizer: Negatable,
+                max_morphisms: 3,
+            }
+            .shortest_path_query(&category, &query)
+            .unwrap()
+        )
+    );
+}
+
+#[test]
+fn filtered_searches_skip_excluded_morphisms() {
+    let category = category();
+    let query =
+        Query::new(0, 3, ()).excluding(|m: &Morphism<u8, Toll>| m.metadata.family == "bridge");
+    let allows = |m: &Morphism<u8, Toll>| query.allows(m);
+    let direct = |path: Option<WellFormedPath<u8, Toll, u8, (), i64>>| {
+        assert_eq!(vec!["direct"], names(path.unwrap()));
+    };
+
+    direct(
+        Accumulating
+            .shortest_path_tree_filtered(&category, 0, (), &Accumulate, &allows)
+            .unwrap()
+            .path_to(&3),
+    );
+    direct(
+        Negatable
+            .shortest_path_tree_filtered(&category, 0, (), &Accumulate, &allows)
+            .unwrap()
+            .unwrap()
+            .path_to(&3),
+    );
+    direct(
+        FloydWarshall
+            .all_pairs_filtered(&category, (), &allows)
+            .path(&0, &3)
+            .unwrap(),
+    );
+    direct(
+        Johnson
+            .all_pairs_filtered(&category, (), &allows)
+            .path(&0, &3)
+            .unwrap(),
+    );
+    direct(
+        CompiledCategory::filtered(&category, (), &allows)
+            .shortest_path(0, 3)
+            .unwrap(),
+    );
+    let paths = KShortest(3).k_shortest_paths_filtered(&category, 0, 3, (), &Accumulate, &allows);
+    assert_eq!(1, paths.len());
+    direct(paths.into_iter().next());
+}
+
+#[test]
+fn query_propagates_sizes_with_the_policy() {
+    let category = quotes::<true>(vec![(0, 1, "a", 2, 1), (1, 2, "b", 3, 1)]);
+    let query = Query::new(0, 2, 5).via(1);
+    let accumulated = Accumulating
+        .shortest_path_query(&category, &query)
+        .safe_unwrap()
+        .unwrap();
+    // a is charged on 5, and b on the 10 that a outputs
+    assert_eq!((vec!["a", "b"], 15), quote_names(accumulated));
+
+    let passed_through = Accumulating
+        .shortest_path_query_propagating(&category, &query, &PassThrough)
+        .safe_unwrap()
+        .unwrap();
+    assert_eq!((vec!["a", "b"], 10), quote_names(passed_through));
+}
+
+#[test]
+fn waypoint_legs_are_greedy() {
+    // "cheap" is the cheapest way to the waypoint, but it multiplies the size
+    // that the next leg is charged on
+    let category = quotes::<true>(vec![
+        (0, 1, "cheap", 10, 0),
+        (0, 1, "small", 1, 1),
+        (1, 2, "leg", 1, 1),
+    ]);
+    let greedy = Accumulating
+        .shortest_path_query(&category, &Query::new(0, 2, 1).via(1))
+        .safe_unwrap()
+        .unwrap();
+    assert_eq!((vec!["cheap", "leg"], 10), quote_names(greedy));
+
+    // every path visits the waypoint, so the unconstrained search is optimal
+    let optimal = Accumulating
+        .shortest_path(&category, 0, 2, 1)
+        .safe_unwrap()
+        .unwrap();
+    assert_eq!((vec!["small", "leg"], 2), quote_names(optimal));
+}
@@ -0,0 +1,167 @@
+use crate::category::Category;
+use crate::impls::Float;
+use crate::morphism::{ApplyMorphism, ConsumeResources, Morphism, MorphismMeta, MorphismOutput};
+use crate::shortest_path::optimizer::Optimizer;
+use crate::shortest_path::path::{AppliedCompositeMorphism, WellFormedPath};
+use crate::{shortest_path::*, InfallibleResultExt};
+
+use super::{quote_names, quotes, Quote};
+
+/// Compute units, and slippage in basis points of the input
+type Resources = [i64; 2];
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Instruction {
+    name: &'static str,
+    cost: i64,
+    compute: i64,
+    slippage_bps: i64,
+}
+
+impl MorphismMeta for Instruction {
+    type Key = &'static str;
+
+    fn key(&self) -> &&'static str {
+        &self.name
+    }
+}
+
+impl ApplyMorphism<i64, i64, true> for Instruction {
+    fn apply(&self, input: i64) -> MorphismOutput<i64, i64> {
+        MorphismOutput {
+            size: input,
+            cost: self.cost,
+        }
+    }
+}
+
+impl ConsumeResources<u8, i64, Resources> for Instruction {
+    fn resources(&self, _source: &u8, _target: &u8, input: &i64) -> Resources {
+        [self.compute, self.slippage_bps * input / 10_000]
+    }
+}
+
+/// The fast entry is cheaper than the lean entry, but uses more compute.
+fn category() -> Category<u8, Instruction> {
+    let instruction = |source, target, name, cost, compute, slippage_bps| {
+        Morphism::new(
+            source,
+            target,
+            Instruction {
+                name,
+                cost,
+                compute,
+                slippage_bps,
+            },
+        )
+    };
+    Category::from_morphisms(vec![
+        instruction(0, 2, "direct", 10, 0, 0),
+        instruction(0, 1, "fast", 1, 5, 0),
+        instruction(0, 1, "lean", 3, 1, 0),
+        instruction(1, 2, "exit", 1, 4, 100),
+    ])
+}
+
+fn names(path: WellFormedPath<u8, Instruction, u8, i64, i64>) -> Vec<&'static str> {
+    let applied: AppliedCompositeMorphism<u8, Instruction, u8, i64, i64> = path.into();
+    applied
+        .morphisms
+        .iter()
+        .map(|m| m.morphism.metadata.name)
+        .collect()
+}
+
+fn cheapest(limits: Resources, input: i64) -> Option<(Vec<&'static str>, i64)> {
+    ResourceConstrained { limits }
+        .shortest_path(&category(), 0, 2, input)
+        .safe_unwrap()
+        .map(|path| {
+            let cost = path.cost;
+            (names(path), cost)
+        })
+}
+
+#[test]
+fn cheapest_path_within_limits() {
+    let unconstrained = Accumulating
+        .shortest_path(&category(), 0, 2, 1000)
+        .safe_unwrap()
+        .unwrap();
+    assert_eq!(2, unconstrained.cost);
+
+    assert_eq!(Some((vec!["fast", "exit"], 2)), cheapest([10, 10], 1000));
+    // the fast entry reaches 1 more cheaply, but it must not prune the lean one
+    assert_eq!(Some((vec!["lean", "exit"], 4)), cheapest([6, 10], 1000));
+    assert_eq!(Some((vec!["direct"], 10)), cheapest([4, 10], 1000));
+    // slippage depends on the input
+    assert_eq!(Some((vec!["direct"], 10)), cheapest([10, 10], 2000));
+    assert_eq!(None, cheapest([-1, 0], 1000));
+}
+
+#[test]
+fn float_resources() {
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    struct Weighted(&'static str, Float);
+
+    impl MorphismMeta for Weighted {
+        type Key = &'static str;
+
+        fn key(&self) -> &&'static str {
+            &self.0
+        }
+    }
+
+    impl ApplyMorphism for Weighted {
+        fn apply(&self, input: Float) -> MorphismOutput {
+            MorphismOutput {
+                size: input,
+                cost: 1.into(),
+            }
+        }
+    }
+
+    impl ConsumeResources<u8, Float, [Float; 1]> for Weighted {
+        fn resources(&self, _source: &u8, _target: &u8, _input: &Float) -> [Float; 1] {
+            [self.1]
+        }
+    }
+
+    let category: Category<u8, Weighted> = Category::from_morphisms(vec![
+        Morphism::new(0, 1, Weighted("heavy", 2.5.into())),
+        Morphism::new(0, 2, Weighted("light", 0.5.into())),
+        Morphism::new(2, 1, Weighted("also light", 0.5.into())),
+    ]);
+    let path = ResourceConstrained {
+        limits: [Float::from(2)],
+    }
+    .shortest_path(&category, 0, 1, Float::from(1))
+    .safe_unwrap()
+    .unwrap();
+    assert_eq!(Float::from(2), path.cost);
+    assert_eq!(5, path.vertices.len());
+}
+
+impl ConsumeResources<u8, i64, [i64; 1]> for Quote {
+    fn resources(&self, _source: &u8, _target: &u8, _input: &i64) -> [i64; 1] {
+        [0]
+    }
+}
+
+#[test]
+fn dominated_labels_are_kept_if_they_can_reach_more_objects() {
+    // 0 -> 1 -> 2 reaches 2 more cheaply than 0 -> 2, but it cannot use the
+    // rebate from 2 back to 1
+    let category = quotes(vec![
+        (0, 1, "a", 1, 0),
+        (0, 2, "b", 1, 1),
+        (1, 2, "c", 1, 0),
+        (2, 1, "d", 1, -10),
+        (1, 3, "e", 1, 0),
+    ]);
+    let path = ResourceConstrained { limits: [0] }
+        .shortest_path(&category, 0, 3, 1)
+        .safe_unwrap()
+        .unwrap();
+    assert_eq!(quote_names(path), (vec!["b", "d", "e"], -9));
+}
@@ -0,0 +1,103 @@
+use serde_json::json;
+
+use crate::category::Category;
+use crate::impls::{DeductiveLinearCost, SimpleMorphism};
+use crate::morphism::Morphism;
+use crate::shortest_path::optimizer::Optimizer;
+use crate::shortest_path::path::WellFormedPath;
+use crate::{shortest_path::*, InfallibleResultExt};
+
+type MyMorphMeta = SimpleMorphism<String, DeductiveLinearCost>;
+type MyMorph = Morphism<u8, MyMorphMeta>;
+
+fn morphism(source: u8, target: u8, rate: i32) -> MyMorph {
+    MyMorph::new(
+        source,
+        target,
+        MyMorphMeta {
+            meta: format!("{source}to{target}"),
+            logic: DeductiveLinearCost {
+                rate: rate.into(),
+                constant: 1.into(),
+            },
+        },
+    )
+}
+
+fn get_category() -> Category<u8, MyMorphMeta> {
+    vec![morphism(0, 1, 1), morphism(1, 2, 2), morphism(0, 2, 10)].into()
+}
+
+#[test]
+fn category_round_trip() {
+    let category = get_category();
+    let json = serde_json::to_string(&category).unwrap();
+    let deserialized: Category<u8, MyMorphMeta> = serde_json::from_str(&json).unwrap();
+
+    let mut expected = category.morphisms().cloned().collect::<Vec<_>>();
+    let mut actual = deserialized.morphisms().cloned().collect::<Vec<_>>();
+    expected.sort_by(|a, b| a.metadata.meta.cmp(&b.metadata.meta));
+    actual.sort_by(|a, b| a.metadata.meta.cmp(&b.metadata.meta));
+    assert_eq!(expected, actual);
+    assert_eq!(1, deserialized.get_outbound(&1).unwrap().count());
+    assert_eq!(2, deserialized.get_inbound(&2).unwrap().count());
+}
+
+#[test]
+fn category_is_verified() {
+    let json = json!({
+        "objects": [0, 1],
+        "morphisms": [
+            {"source": 0, "target": 2, "metadata": {"meta": "0to2", "logic": {"rate": 1.0, "constant": 1.0}}}
+        ]
+    });
+    assert!(serde_json::from_value::<Category<u8, MyMorphMeta>>(json).is_err());
+}
+
+#[test]
+fn path_round_trip() {
+    let path = Accumulating
+        .shortest_path(&get_category(), 0, 2, 10.into())
+        .safe_unwrap()
+        .unwrap();
+    let json = serde_json::to_string(&path).unwrap();
+    let deserialized: WellFormedPath<u8, MyMorphMeta> = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(path.vertices, deserialized.vertices);
+    assert_eq!(path.cost, deserialized.cost);
+}
+
+#[test]
+fn path_structure_is_verified() {
+    let object = |id: u8| json!({"Object": {"inner": id, "size": 1.0}});
+    let morphism = json!({"Morphism": {
+        "inner": {"source": 0, "target": 1, "metadata": {"meta": "0to1", "logic": {"rate": 1.0, "constant": 1.0}}},
+        "input": 1.0,
+        "cost": 1.0
+    }});
+    let deserialize = |vertices: serde_json::Value| {
+        serde_json::from_value::<WellFormedPath<u8, MyMorphMeta>>(
+            json!({"vertices": vertices, "cost": 1.0}),
+        )
+    };
+
+    assert!(deserialize(json!([object(0), morphism.clone(), object(1)])).is_ok());
+    assert!(deserialize(json!([])).is_err());
+    assert!(deserialize(json!([object(0), morphism.clone()])).is_err());
+    assert!(deserialize(json!([object(0), object(1), object(1)])).is_err());
+    assert!(deserialize(json!([morphism.clone(), morphism.clone(), object(1)])).is_err());
+}
+
+#[test]
+fn path_morphisms_must_connect_their_objects() {
+    let object = |id: u8| json!({"Object": {"inner": id, "size": 1.0}});
+    let morphism = json!({"Morphism": {
+        "inner": {"source": 0, "target": 1, "metadata": {"meta": "0to1", "logic": {"rate": 1.0, "constant": 1.0}}},
+        "input": 1.0,
+        "cost": 1.0
+    }});
+    let deserialized = serde_json::from_value::<WellFormedPath<u8, MyMorphMeta>>(
+        json!({"vertices": [object(0), morphism, object(2)], "cost": 1.0}),
+    );
+    assert!(deserialized.is_err());
+}
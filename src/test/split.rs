@@ -0,0 +1,182 @@
+use crate::category::Category;
+use crate::impls::Float;
+use crate::morphism::{ApplyMorphism, Morphism, MorphismMeta, MorphismOutput};
+use crate::propagation::Accumulate;
+use crate::shortest_path::optimizer::Optimizer;
+use crate::shortest_path::path::{SplitObjective, SplitRoute};
+use crate::{shortest_path::*, InfallibleResultExt};
+
+/// A constant product pool with equal reserves of `depth`. The cost is the
+/// slippage, which grows faster than the input.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Pool {
+    name: &'static str,
+    depth: Float,
+}
+
+impl MorphismMeta for Pool {
+    type Key = &'static str;
+
+    fn key(&self) -> &&'static str {
+        &self.name
+    }
+}
+
+impl ApplyMorphism<Float, Float, true> for Pool {
+    fn apply(&self, input: Float) -> MorphismOutput {
+        let size = input * self.depth / (self.depth + input);
+        MorphismOutput {
+            size,
+            cost: input - size,
+        }
+    }
+}
+
+fn pools(pools: Vec<(u8, u8, &'static str, i32)>) -> Category<u8, Pool> {
+    let mut category = Category::new();
+    category.add_objects(vec![0, 1, 2]).unwrap();
+    category
+        .add_morphisms(
+            pools
+                .into_iter()
+                .map(|(source, target, name, depth)| {
+                    Morphism::new(
+                        source,
+                        target,
+                        Pool {
+                            name,
+                            depth: depth.into(),
+                        },
+                    )
+                })
+                .collect::<Vec<_>>(),
+        )
+        .unwrap();
+    category
+}
+
+fn category(depths: Vec<(&'static str, i32)>) -> Category<u8, Pool> {
+    pools(
+        depths
+            .into_iter()
+            .map(|(name, depth)| (0, 1, name, depth))
+            .collect(),
+    )
+}
+
+fn amount_of(route: &SplitRoute<u8, Pool>, name: &str) -> Float {
+    route
+        .routes
+        .iter()
+        .find(|(path, _)| path.morphisms.first().morphism.metadata.name == name)
+        .map(|(_, amount)| *amount)
+        .unwrap_or(0.into())
+}
+
+#[test]
+fn splits_evenly_between_equal_pools() {
+    let category = category(vec![("a", 100), ("b", 100)]);
+    let single = Accumulating
+        .shortest_path(&category, 0, 1, 100.into())
+        .safe_unwrap()
+        .unwrap();
+    assert_eq!(Float::from(50), single.cost);
+
+    for objective in [SplitObjective::MinCost, SplitObjective::MaxOutput] {
+        let route = SplitRouting {
+            paths: 2,
+            chunks: 10,
+            objective,
+        }
+        .split_route(&category, 0, 1, Float::from(100))
+        .unwrap();
+        assert_eq!(2, route.routes.len());
+        assert_eq!(Float::from(50), amount_of(&route, "a"));
+        assert_eq!(Float::from(50), amount_of(&route, "b"));
+        assert!(route.cost < single.cost);
+        assert_eq!(Float::from(100) - route.cost, route.output);
+        for (path, _) in &route.routes {
+            assert_eq!(Float::from(50), path.input());
+        }
+    }
+}
+
+#[test]
+fn deeper_pool_receives_more() {
+    let route = SplitRouting {
+        paths: 2,
+        chunks: 8,
+        objective: SplitObjective::MaxOutput,
+    }
+    .split_route(
+        &category(vec![("shallow", 100), ("deep", 300)]),
+        0,
+        1,
+        Float::from(80),
+    )
+    .unwrap();
+    assert_eq!(Float::from(20), amount_of(&route, "shallow"));
+    assert_eq!(Float::from(60), amount_of(&route, "deep"));
+
+    let single = SplitRouting {
+        paths: 1,
+        chunks: 8,
+        objective: SplitObjective::MaxOutput,
+    }
+    .split_route(
+        &category(vec![("shallow", 100), ("deep", 300)]),
+        0,
+        1,
+        Float::from(80),
+    )
+    .unwrap();
+    assert_eq!(1, single.routes.len());
+    assert_eq!(Float::from(80), amount_of(&single, "deep"));
+    assert!(single.output < route.output);
+}
+
+#[test]
+fn excluded_pools_receive_nothing() {
+    let route = SplitRouting {
+        paths: 2,
+        chunks: 10,
+        objective: SplitObjective::MinCost,
+    }
+    .split_route_filtered(
+        &category(vec![("a", 100), ("b", 100)]),
+        0,
+        1,
+        Float::from(100),
+        &Accumulate,
+        &|m: &Morphism<u8, Pool>| m.metadata.name != "b",
+    )
+    .unwrap();
+    assert_eq!(1, route.routes.len());
+    assert_eq!(Float::from(100), amount_of(&route, "a"));
+}
+
+#[test]
+fn candidates_do_not_share_pools() {
+    // both routes to 2 go through "shared", so splitting between them would
+    // price "shared" twice on half of the input instead of once on all of it
+    let category = pools(vec![
+        (0, 1, "a", 1000),
+        (0, 1, "b", 1000),
+        (1, 2, "shared", 100),
+    ]);
+    let route = SplitRouting {
+        paths: 2,
+        chunks: 10,
+        objective: SplitObjective::MinCost,
+    }
+    .split_route(&category, 0, 2, Float::from(100))
+    .unwrap();
+    let single = Accumulating
+        .shortest_path(&category, 0, 2, 100.into())
+        .safe_unwrap()
+        .unwrap();
+
+    assert_eq!(1, route.routes.len());
+    assert_eq!(Float::from(100), route.routes[0].1);
+    assert_eq!(single.cost, route.cost);
+}
@@ -0,0 +1,160 @@
+use std::collections::HashSet;
+
+use crate::category::Category;
+use crate::shortest_path::optimizer::Optimizer;
+use crate::{shortest_path::*, InfallibleResultExt};
+
+use super::{quote_names as names, Quote};
+
+fn category<const NON_NEGATIVE: bool>(
+    quotes: Vec<(u8, u8, &'static str, i64, i64)>,
+) -> Category<u8, Quote<NON_NEGATIVE>> {
+    let mut category = super::quotes(quotes);
+    category.add_object(7).unwrap();
+    category
+}
+
+/// Tokens 1 to 6 are reachable from 0 in several ways, and 7 is not reachable.
+fn tokens<const NON_NEGATIVE: bool>(fee: i64) -> Category<u8, Quote<NON_NEGATIVE>> {
+    category(vec![
+        (0, 1, "a", 2, 1),
+        (0, 2, "b", 1, 3),
+        (1, 2, "c", 1, 1),
+        (1, 3, "d", 3, 3),
+        (2, 3, "e", 1, 1),
+        (3, 4, "f", 1, fee),
+        (2, 4, "g", 1, 4),
+        (4, 5, "h", 1, 1),
+        (5, 6, "i", 1, 1),
+    ])
+}
+
+#[test]
+fn dijkstra_tree_matches_a_search_per_target() {
+    let category = tokens::<true>(1);
+    let tree = Accumulating.shortest_path_tree(&category, 0, 10).unwrap();
+
+    assert_eq!(
+        tree.reachable().copied().collect::<HashSet<_>>(),
+        HashSet::from([1, 2, 3, 4, 5, 6])
+    );
+    for target in [1, 2, 3, 4, 5, 6] {
+        let expected = Accumulating
+            .shortest_path(&category, 0, target, 10)
+            .safe_unwrap()
+            .unwrap();
+        assert_eq!(tree.cost_to(&target), Some(expected.cost));
+        assert_eq!(
+            names(tree.path_to(&target).unwrap()),
+            names(expected),
+            "{target}"
+        );
+    }
+    assert_eq!(tree.size_at(&1), Some(&20));
+    assert!(tree.path_to(&7).is_none());
+    assert!(tree.path_to(&0).is_none());
+    assert!(Accumulating.shortest_path_tree(&category, 8, 10).is_none());
+}
+
+#[test]
+fn unreachable_objects_are_not_in_the_tree() {
+    let category = tokens::<true>(1);
+    let tree = Accumulating.shortest_path_tree(&category, 4, 10).unwrap();
+
+    assert_eq!(
+        tree.reachable().copied().collect::<HashSet<_>>(),
+        HashSet::from([5, 6])
+    );
+    assert!(tree.path_to(&0).is_none());
+    assert!(tree.cost_to(&3).is_none());
+    assert_eq!(names(tree.path_to(&6).unwrap()), (vec!["h", "i"], 20));
+}
+
+#[test]
+fn bellman_ford_tree_matches_a_search_per_target() {
+    let category = tokens::<false>(-5);
+    let tree = Negatable
+        .shortest_path_tree(&category, 0, 10)
+        .unwrap()
+        .unwrap();
+
+    for target in [1, 2, 3, 4, 5, 6] {
+        let expected = Negatable
+            .shortest_path(&category, 0, target, 10)
+            .unwrap()
+            .unwrap();
+        assert_eq!(tree.cost_to(&target), Some(expected.cost));
+        assert_eq!(
+            names(tree.path_to(&target).unwrap()),
+            names(expected),
+            "{target}"
+        );
+    }
+    assert!(Negatable
+        .shortest_path_tree(&category, 8, 10)
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn accumulating_shortest_paths_matches_a_search_per_pair() {
+    let category = tokens::<true>(1);
+    let paths = Accumulating
+        .shortest_paths(&category, vec![(0, 10), (1, 10)], vec![0, 4, 6])
+        .safe_unwrap();
+
+    let expected = [(0, 4), (0, 6), (1, 4), (1, 6)].map(|(source, target)| {
+        names(
+            Accumulating
+                .shortest_path(&category, source, target, 10)
+                .safe_unwrap()
+                .unwrap(),
+        )
+    });
+    assert_eq!(paths.into_iter().map(names).collect::<Vec<_>>(), expected);
+}
+
+#[test]
+fn dijkstra_tree_matches_a_search_per_target_with_accumulation() {
+    // "cheap" is the cheapest way to 1, but it multiplies the size that "leg"
+    // is charged on
+    let category = category::<true>(vec![
+        (0, 1, "cheap", 10, 0),
+        (0, 1, "small", 1, 1),
+        (1, 2, "leg", 1, 1),
+    ]);
+    let tree = Accumulating.shortest_path_tree(&category, 0, 1).unwrap();
+
+    assert_eq!(names(tree.path_to(&1).unwrap()), (vec!["cheap"], 0));
+    assert_eq!(names(tree.path_to(&2).unwrap()), (vec!["small", "leg"], 2));
+    assert_eq!(tree.size_at(&2), Some(&1));
+    for target in [1, 2] {
+        let expected = Accumulating
+            .shortest_path(&category, 0, target, 1)
+            .safe_unwrap()
+            .unwrap();
+        assert_eq!(names(tree.path_to(&target).unwrap()), names(expected));
+    }
+}
+
+#[test]
+fn bellman_ford_tree_reports_negative_cycles() {
+    let category = category::<false>(vec![
+        (0, 1, "a", 1, 1),
+        (1, 2, "b", 1, -3),
+        (2, 1, "c", 1, 1),
+    ]);
+
+    let Err(PathFindingError::NegativeCycle(cycle)) =
+        Negatable.shortest_path_tree(&category, 0, 10)
+    else {
+        panic!("expected a negative cycle");
+    };
+    assert_eq!(
+        cycle
+            .iter()
+            .map(|m| m.metadata.name)
+            .collect::<HashSet<_>>(),
+        HashSet::from(["b", "c"])
+    );
+}
@@ -3,36 +3,127 @@
 //! edges in the graph represent the fact that objects and morphisms are
 //! connected.
 
-use std::{collections::HashSet, hash::Hash};
-
-use pathfinding::num_traits::Zero;
+use std::hash::Hash;
 
 use crate::{
     category::{Category, HasId, Key, Object},
+    cost::CostMeasure,
     impls::Float,
-    morphism::ApplyMorphism,
-    morphism::Morphism,
+    morphism::{Morphism, MorphismMeta, TryApplyMorphism},
+    propagation::SizePropagation,
 };
 
 /// Comprehensive return type that includes the full object
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
-pub enum Vertex<Id, M, Obj = Id, Size = Float> {
-    Object { inner: Obj, size: Size },
-    Morphism { inner: Morphism<Id, M>, input: Size },
+///
+/// A morphism's cost is the cost of applying it to its input, not including
+/// the cost of any other morphism in the path.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Vertex<Id, M, Obj = Id, Size = Float, Cost = Float> {
+    Object {
+        inner: Obj,
+        size: Size,
+    },
+    Morphism {
+        inner: Morphism<Id, M>,
+        input: Size,
+        cost: Cost,
+    },
+}
+
+// written by hand because a morphism is compared by its key, which the derive
+// cannot express as a bound on M
+impl<Id, M, Obj, Size, Cost> PartialEq for Vertex<Id, M, Obj, Size, Cost>
+where
+    Id: PartialEq,
+    M: MorphismMeta,
+    Obj: PartialEq,
+    Size: PartialEq,
+    Cost: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                Vertex::Object { inner, size },
+                Vertex::Object {
+                    inner: other_inner,
+                    size: other_size,
+                },
+            ) => inner == other_inner && size == other_size,
+            (
+                Vertex::Morphism { inner, input, cost },
+                Vertex::Morphism {
+                    inner: other_inner,
+                    input: other_input,
+                    cost: other_cost,
+                },
+            ) => inner == other_inner && input == other_input && cost == other_cost,
+            _ => false,
+        }
+    }
+}
+
+impl<Id, M, Obj, Size, Cost> Eq for Vertex<Id, M, Obj, Size, Cost>
+where
+    Id: Eq,
+    M: MorphismMeta,
+    Obj: Eq,
+    Size: Eq,
+    Cost: Eq,
+{
+}
+
+impl<Id, M, Obj, Size, Cost> Hash for Vertex<Id, M, Obj, Size, Cost>
+where
+    Id: Hash,
+    M: MorphismMeta,
+    Obj: Hash,
+    Size: Hash,
+    Cost: Hash,
+{
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Vertex::Object { inner, size } => {
+                inner.hash(state);
+                size.hash(state);
+            }
+            Vertex::Morphism { inner, input, cost } => {
+                inner.hash(state);
+                input.hash(state);
+                cost.hash(state);
+            }
+        }
+    }
 }
 
-impl<Obj, Id, M, Size> Vertex<Id, M, Obj, Size> {
+impl<Obj, Id, M, Size> Vertex<Id, M, Obj, Size, ()> {
     pub(crate) fn from(lean: LeanVertex<Id, M, Size>, category: &Category<Id, M, Obj>) -> Self
     where
         Obj: Object<Id>,
         Id: Key,
+        M: MorphismMeta,
     {
         match lean {
             LeanVertex::Object { inner: id, size } => Self::Object {
                 inner: category.get_object(&id).unwrap().clone(), //todo unwrap
                 size,
             },
-            LeanVertex::Morphism { inner, input } => Self::Morphism { inner, input },
+            LeanVertex::Morphism { inner, input, .. } => Self::Morphism {
+                inner,
+                input,
+                cost: (),
+            },
+        }
+    }
+}
+
+impl<Obj, Id, M, Size, Cost> Vertex<Id, M, Obj, Size, Cost> {
+    /// The cost of this step in the path, if it is a morphism
+    pub fn cost(&self) -> Option<&Cost> {
+        match self {
+            Vertex::Object { .. } => None,
+            Vertex::Morphism { cost, .. } => Some(cost),
         }
     }
 
@@ -49,8 +140,9 @@ impl<Obj, Id, M, Size> Vertex<Id, M, Obj, Size> {
 }
 
 /// Used as a vertex in the underlying graph optimization algorithms. Only
-/// refers to an object by its id, to keep things simple and lightweight.
-pub(crate) type LeanVertex<Id, M, Size> = Vertex<Id, M, Id, Size>;
+/// refers to an object by its id, to keep things simple and lightweight. Costs
+/// are tracked by the algorithms rather than the vertices.
+pub(crate) type LeanVertex<Id, M, Size> = Vertex<Id, M, Id, Size, ()>;
 
 impl<Id, M, Size> Default for LeanVertex<Id, M, Size> {
     fn default() -> Self {
@@ -60,27 +152,31 @@ impl<Id, M, Size> Default for LeanVertex<Id, M, Size> {
 
 // TODO: consolidate
 impl<Id, M, Size> LeanVertex<Id, M, Size> {
-    pub fn successors<const NON_NEGATIVE: bool, Obj, Cost: Zero>(
+    pub fn successors<const NON_NEGATIVE: bool, Obj, Cost: CostMeasure, P>(
         &self,
         category: &Category<Id, M, Obj>,
+        original: &Size,
+        propagation: &P,
+        allows: &impl Fn(&Morphism<Id, M>) -> bool,
     ) -> Vec<(LeanVertex<Id, M, Size>, Cost)>
     where
         Id: Key,
         Obj: HasId<Id>,
-        M: ApplyMorphism<Size, Cost, NON_NEGATIVE>,
-        M: Clone,
+        M: MorphismMeta + TryApplyMorphism<Obj, Size, Cost, NON_NEGATIVE>,
         Size: Clone,
+        P: SizePropagation<Obj, Size>,
     {
         match self {
             LeanVertex::Object { inner: id, size } => category
                 .get_outbound(id)
                 .expect("The object id was not found in the category") //todo
-                .iter()
+                .filter(|m| allows(m))
                 .map(|m| {
                     (
                         LeanVertex::Morphism {
                             inner: m.clone(),
                             input: size.clone(),
+                            cost: (),
                         },
                         Cost::zero(),
                     )
@@ -89,59 +185,12 @@ impl<Id, M, Size> LeanVertex<Id, M, Size> {
             LeanVertex::Morphism {
                 inner,
                 input: input_size,
-            } => {
-                let (id, size, cost) = inner.successor(category, input_size.clone());
-                vec![(LeanVertex::Object { inner: id, size }, cost)]
-            }
-        }
-    }
-
-    /// Avoids cycles by preventing re-visit of any objects.
-    pub fn blacklisted_successors<const NON_NEGATIVE: bool, Obj, Cost: Zero>(
-        &self,
-        category: &Category<Id, M, Obj>,
-        blacklist: &mut HashSet<Id>,
-    ) -> Vec<(LeanVertex<Id, M, Size>, Cost)>
-    where
-        Id: Key,
-        Obj: HasId<Id>,
-        M: ApplyMorphism<Size, Cost, NON_NEGATIVE>,
-        M: Clone,
-        Size: Clone,
-    {
-        match self {
-            LeanVertex::Object { inner: id, size } => {
-                blacklist.insert(id.clone());
-                category
-                    .get_outbound(id)
-                    .expect("The object id was not found in the category") //todo
-                    .iter()
-                    .filter_map(|m| {
-                        if blacklist.contains(&m.target) {
-                            None
-                        } else {
-                            Some((
-                                LeanVertex::Morphism {
-                                    inner: m.clone(),
-                                    input: size.clone(),
-                                },
-                                Cost::zero(),
-                            ))
-                        }
-                    })
-                    .collect()
-            }
-            LeanVertex::Morphism {
-                inner,
-                input: input_size,
-            } => {
-                let (id, size, cost) = inner.successor(category, input_size.clone());
-                if blacklist.contains(&id) {
-                    vec![]
-                } else {
-                    vec![(LeanVertex::Object { inner: id, size }, cost)]
-                }
-            }
+                ..
+            } => inner
+                .successor(category, input_size.clone(), original, propagation)
+                .map(|(id, size, cost)| (LeanVertex::Object { inner: id, size }, cost))
+                .into_iter()
+                .collect(),
         }
     }
 }